@@ -1,6 +1,6 @@
 use iced::{
     time,
-    widget::{button, column, pick_list, row, text, vertical_space},
+    widget::{button, column, pick_list, row, text, text_input, vertical_space},
     Element, Subscription, Theme,
 };
 mod ui;
@@ -14,6 +14,7 @@ struct MainWindow {
     selected_maze_type: Option<MazeType>,
     selected_algorithm: Option<Algorithm>,
     selected_generator: Option<Algorithm>,
+    seed_input: String,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +22,8 @@ enum Message {
     MazeTypeSelected(MazeType),
     AlgorithmSelected(Algorithm),
     GeneratorSelected(Algorithm),
+    SeedChanged(String),
+    GenerateMaze,
     FindPath,
     MazeGrid(ui::maze_grid::Message),
     Tick,
@@ -51,6 +54,17 @@ impl MainWindow {
                     }
                 }
             }
+            Message::SeedChanged(seed) => {
+                if seed.chars().all(|c| c.is_ascii_digit()) {
+                    self.seed_input = seed;
+                }
+            }
+            Message::GenerateMaze => {
+                // An empty seed asks for a new random maze
+                let seed = self.seed_input.parse().unwrap_or_else(|_| rand::random());
+                self.maze_grid
+                    .update(ui::maze_grid::Message::GenerateMaze(seed));
+            }
             Message::MazeGrid(message) => {
                 self.maze_grid.update(message);
             }
@@ -61,7 +75,7 @@ impl MainWindow {
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let maze_type_selector_list = pick_list(
             MazeType::cell_types(),
             self.selected_maze_type,
@@ -84,19 +98,34 @@ impl MainWindow {
         .placeholder("Choose a maze generator");
 
         let button_controls = row![
-            button("Generate maze")
-                .on_press(Message::MazeGrid(ui::maze_grid::Message::GenerateMaze)),
+            button("Generate maze").on_press(Message::GenerateMaze),
+            button("Add swamps").on_press(Message::MazeGrid(ui::maze_grid::Message::AddTerrain)),
+            button("Braid").on_press(Message::MazeGrid(ui::maze_grid::Message::Braid)),
             button("Find path").on_press(Message::FindPath),
+            button("Stop").on_press(Message::MazeGrid(ui::maze_grid::Message::Stop)),
         ]
         .spacing(10);
 
+        let (level, levels) = self.maze_grid.level();
+        let level_controls = (levels > 1).then(|| {
+            row![
+                button("Level down").on_press(Message::MazeGrid(ui::maze_grid::Message::LevelDown)),
+                button("Level up").on_press(Message::MazeGrid(ui::maze_grid::Message::LevelUp)),
+                text(format!("Level: {}/{}", level + 1, levels)),
+            ]
+            .spacing(10)
+        });
+
         let left_controls = column![
             text("Maze crawler").size(20),
             maze_type_selector_list,
             algorithm_selector_list,
             generator_selector_list,
+            text_input("Seed (random if empty)", &self.seed_input).on_input(Message::SeedChanged),
+            text(format!("Seed: {}", self.maze_grid.seed())),
             button_controls,
         ]
+        .push_maybe(level_controls)
         .spacing(10);
 
         let left_space = vertical_space().width(5);
@@ -119,6 +148,7 @@ impl Default for MainWindow {
             selected_maze_type: Some(MazeType::default()),
             selected_algorithm: Some(Algorithm::default()),
             selected_generator: Some(Algorithm::DFS),
+            seed_input: String::new(),
         }
     }
 }
@@ -1,9 +1,4 @@
-use std::{
-    cmp::{max, min},
-    collections::VecDeque,
-    sync::mpsc::{channel, Receiver, Sender},
-    thread,
-};
+use std::cmp::{max, min};
 
 use iced::{
     alignment, mouse,
@@ -14,74 +9,133 @@ use iced::{
     Color, Element, Length, Point, Rectangle, Renderer, Size, Theme,
 };
 
-use maze_lib::{algorithms::*, CellType, Maze, MazeCell, MazeType, SlimWallsCellType};
+use maze_lib::{algorithms::*, CellType, Maze, MazeCell, MazeType, SlimWallsCellType, Terrain};
+use rand::{rngs::StdRng, SeedableRng};
 
 use super::AnimationState;
 
+/// Width and height of generated mazes, polar mazes get one ring for every two rows and
+/// layered mazes levels of half the size.
+const MAZE_SIZE: usize = 41;
+/// Xored into the maze seed to pick the dead ends to braid.
+const BRAID_SEED: u64 = 0x6272_6169_6400;
+/// Xored into the maze seed to scatter the swamps.
+const TERRAIN_SEED: u64 = 0x7377_616d_7000;
+
 #[derive(Debug)]
 pub struct MazeGrid {
     maze: Maze,
+    /// Displayed level of a layered maze
+    level: usize,
     grid_cache: Cache,
-    animation_queue: VecDeque<Maze>,
+    /// Running generator, its frames carve `maze` one per tick
+    generation: Option<FramePump<Maze>>,
+    /// Running search, an expansion is applied to `maze` per tick
+    search: Option<FramePump<PathfindingOutcome>>,
+    /// Budget of the running generator or search, cancelled by `Message::Stop`
+    budget: Budget,
     animation_state: AnimationState,
     pub selected_algorithm: Algorithm,
     pub selected_generator: Algorithm,
     pub selected_maze_type: MazeType,
+    seed: u64,
     pathfinding_stats: Option<PathfindingStats>,
+    /// Result of the last search, shown once its animation is over
+    pathfinding_outcome: Option<PathfindingOutcome>,
+    /// Why the last generator or search failed
+    error: Option<PathfindingError>,
     pathfinding_state: PathfindingState,
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum Message {
-    GenerateMaze,
+    GenerateMaze(u64),
+    AddTerrain,
+    Braid,
     SelectAlgorithm(Algorithm),
     SelectMazeType(MazeCell),
+    LevelUp,
+    LevelDown,
+    Stop,
 }
 
 impl MazeGrid {
     pub fn new() -> Self {
         let selected_generator = Algorithm::DFS;
+        let seed = rand::random();
         let maze = selected_generator
             .get_maze_generator()
             .expect("Default generator should exist")
             .generate(
                 maze_lib::MazeType::Thick,
-                41,
-                41,
+                MAZE_SIZE,
+                MAZE_SIZE,
                 maze_lib::algorithms::Point { x: 1, y: 1 },
+                seed,
                 None,
             )
             .unwrap();
         MazeGrid {
             maze,
+            level: 0,
             grid_cache: Cache::default(),
             selected_algorithm: selected_generator,
             selected_generator,
-            animation_queue: VecDeque::new(),
+            generation: None,
+            search: None,
+            budget: Budget::new(),
             animation_state: AnimationState::default(),
             selected_maze_type: MazeType::Thick,
+            seed,
             pathfinding_stats: None,
+            pathfinding_outcome: None,
+            error: None,
             pathfinding_state: PathfindingState::default(),
         }
     }
 
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         let canvas = Canvas::new(self)
             .width(Length::FillPortion(4))
             .height(Length::Fill);
 
         // Stats
+        let mut lines = Vec::new();
         if let Some(st) = self.pathfinding_stats {
-            let steps = text(format!("Steps: {}", st.steps)).align_x(alignment::Horizontal::Left);
-            let stats = column!(steps).width(Length::Shrink).padding(5);
-            let stats_container = container(stats).width(Length::FillPortion(1));
-            row![canvas, stats_container]
-                .align_y(iced::alignment::Vertical::Top)
-                .into()
-        } else {
-            canvas.into()
+            lines.push(format!("Steps: {}", st.steps));
+        }
+        if let Some(outcome) = self.pathfinding_outcome.as_ref() {
+            if let Some(partial) = &outcome.stopped {
+                lines.push(PathfindingError::Stopped(partial.reason).to_string());
+                lines.push(format!("Visited: {} cells", partial.visited.len()));
+                lines.push(format!("Frames: {}", partial.frames));
+            } else if outcome.reachable {
+                lines.push(format!("Path: {} cells", outcome.path.len()));
+                lines.push(format!("Cost: {}", outcome.cost));
+            } else {
+                lines.push(PathfindingError::Unreachable.to_string());
+            }
+            lines.push(format!("Expanded: {}", outcome.expanded));
         }
+        if let Some(error) = self.error {
+            lines.push(error.to_string());
+        }
+
+        if lines.is_empty() {
+            return canvas.into();
+        }
+        let stats = column(
+            lines
+                .into_iter()
+                .map(|line| text(line).align_x(alignment::Horizontal::Left).into()),
+        )
+        .width(Length::Shrink)
+        .padding(5);
+        let stats_container = container(stats).width(Length::FillPortion(1));
+        row![canvas, stats_container]
+            .align_y(iced::alignment::Vertical::Top)
+            .into()
     }
 
     pub fn update(&mut self, message: Message) {
@@ -90,118 +144,340 @@ impl MazeGrid {
                 if algorithm.is_pathfinding_algorithm() {
                     self.selected_algorithm = algorithm;
                     self.grid_cache.clear();
-                    self.animation_queue.clear();
+                    self.cancel_search();
                 }
             }
-            Message::GenerateMaze => self.generate_maze(),
+            Message::GenerateMaze(seed) => self.generate_maze(seed),
+            Message::AddTerrain => self.add_terrain(),
+            Message::Braid => self.braid(),
             Message::SelectMazeType(_) => todo!(),
+            Message::LevelUp => {
+                self.level = (self.level + 1).min(self.maze.levels() - 1);
+                self.grid_cache.clear();
+            }
+            Message::LevelDown => {
+                self.level = self.level.saturating_sub(1);
+                self.grid_cache.clear();
+            }
+            Message::Stop => {
+                // The runs are cut off at their next frame and show how far they got
+                self.budget.cancel();
+                self.finish_generation();
+                self.finish_search();
+            }
         }
     }
 
     pub fn tick(&mut self) {
-        if !self.animation_queue.is_empty() {
+        if let Some(generation) = self.generation.as_mut() {
             self.animation_state = AnimationState::Running;
-            self.maze = self
-                .animation_queue
-                .pop_front()
-                .expect("Cannot pop animation frame");
+            if let Some(frame) = generation.step() {
+                frame.apply(&mut self.maze);
+            }
+            if generation.is_done() {
+                self.finish_generation();
+            }
+            self.grid_cache.clear();
+        } else if let Some(search) = self.search.as_mut() {
+            self.animation_state = AnimationState::Running;
+            if let Some(frame) = search.step() {
+                frame.apply(&mut self.maze);
+                if let Some(stats) = frame.stats() {
+                    self.pathfinding_stats = Some(stats);
+                }
+            }
+            if search.is_done() {
+                self.finish_search();
+            }
             self.grid_cache.clear();
         } else {
             self.animation_state = AnimationState::NotRunning;
         }
     }
 
+    fn is_animating(&self) -> bool {
+        self.generation.is_some() || self.search.is_some()
+    }
+
+    /// Drops the running search together with the cells it marked.
+    fn cancel_search(&mut self) {
+        if self.search.take().is_none() {
+            return;
+        }
+        self.budget.cancel();
+        self.maze = self.maze.from_original();
+        self.pathfinding_stats = None;
+        self.pathfinding_state = PathfindingState::NotStarted;
+    }
+
+    fn finish_generation(&mut self) {
+        let Some(generation) = self.generation.take() else {
+            return;
+        };
+        match generation.finish() {
+            Ok(maze) => self.maze = maze,
+            Err(error) => self.error = Some(error),
+        }
+        // A stopped generator leaves the passages carved so far
+        if let Some(reason) = self.budget.stop_reason() {
+            self.error = Some(PathfindingError::Stopped(reason));
+        }
+        self.grid_cache.clear();
+    }
+
+    fn finish_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        match search.finish() {
+            Ok(outcome) => self.pathfinding_outcome = Some(outcome),
+            Err(error) => self.error = Some(error),
+        }
+        self.pathfinding_state = PathfindingState::Finished;
+        self.grid_cache.clear();
+    }
+
     pub fn start(&mut self) {
         // Don't start pathfinding if the selected algorithm is not a pathfinding algorithm,
-        // or animation queue is not empty
-        if !self.selected_algorithm.is_pathfinding_algorithm() || !self.animation_queue.is_empty() {
+        // or an animation is running
+        if !self.selected_algorithm.is_pathfinding_algorithm() || self.is_animating() {
             return;
         }
+        let Some(pathfinder) = self.selected_algorithm.get_pathfinder() else {
+            return;
+        };
 
         // Reset maze
         self.pathfinding_stats = None;
+        self.pathfinding_outcome = None;
+        self.error = None;
         self.grid_cache.clear();
-        self.animation_queue.clear();
         self.pathfinding_state = PathfindingState::Running;
         self.maze = self.maze.from_original();
 
-        let (sender, receiver): (Sender<PathfindingResult>, Receiver<PathfindingResult>) =
-            channel();
+        self.budget = Budget::new();
+        self.search = Some(FramePump::find_path_within(
+            pathfinder,
+            self.maze.clone(),
+            self.budget.clone(),
+        ));
+    }
 
-        let mut maze = self.maze.clone();
-        let selected_algorithm = self.selected_algorithm;
+    fn add_terrain(&mut self) {
+        if self.is_animating() {
+            return;
+        }
 
-        let handle = thread::spawn(move || match selected_algorithm {
-            Algorithm::AStar => {
-                let mut astar = AStar::new();
-                astar.find_path(&mut maze, &sender);
-            }
-            Algorithm::Backtracking => {
-                let mut backtracking = Backtracking::new();
-                backtracking.find_path(&mut maze, &sender);
-            }
-            Algorithm::BellmanFord => {
-                let mut bellman_ford = BellmanFord;
-                bellman_ford.find_path(&mut maze, &sender);
-            }
-            Algorithm::BFS => {
-                let mut bfs = BFS::new();
-                bfs.find_path(&mut maze, &sender);
-            }
-            Algorithm::DFS => {
-                let mut dfs = DFS::new();
-                dfs.find_path(&mut maze, &sender);
-            }
-            Algorithm::Dijkstra => {
-                let mut dijktra = Dijkstra::new();
-                dijktra.find_path(&mut maze, &sender);
-            }
-            _ => unreachable!("Non-pathfinding algorithms should be filtered out"),
-        });
+        self.maze = self.maze.from_original();
+        self.maze
+            .scatter_terrain(&mut StdRng::seed_from_u64(self.seed ^ TERRAIN_SEED), 0.15);
+        self.maze.backup();
+        self.pathfinding_stats = None;
+        self.pathfinding_outcome = None;
+        self.pathfinding_state = PathfindingState::NotStarted;
+        self.grid_cache.clear();
+    }
 
-        while let Ok(received_result) = receiver.recv() {
-            self.maze = received_result.maze.clone();
-            self.animation_queue.push_back(received_result.maze);
-            self.pathfinding_stats = received_result.stats;
+    /// Opens half of the dead ends, so the pathfinders have more than one route to choose.
+    fn braid(&mut self) {
+        if self.is_animating() {
+            return;
         }
 
-        handle.join().expect("Failed to join thread");
+        self.maze = self.maze.from_original();
+        self.maze
+            .braid(&mut StdRng::seed_from_u64(self.seed ^ BRAID_SEED), 0.5);
+        self.maze.backup();
+        self.pathfinding_stats = None;
+        self.pathfinding_outcome = None;
+        self.pathfinding_state = PathfindingState::NotStarted;
         self.grid_cache.clear();
+    }
 
-        self.pathfinding_state = PathfindingState::Finished;
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Displayed level and number of levels, a single one unless the maze is layered.
+    pub fn level(&self) -> (usize, usize) {
+        (self.level, self.maze.levels())
     }
 
-    fn generate_maze(&mut self) {
+    fn generate_maze(&mut self, seed: u64) {
+        // Whatever runs belongs to the old maze
+        self.budget.cancel();
+        self.generation = None;
+        self.search = None;
+
+        self.seed = seed;
+        self.level = 0;
+        self.pathfinding_stats = None;
+        self.pathfinding_outcome = None;
+        self.error = None;
         self.grid_cache.clear();
-        self.animation_queue.clear();
-        self.pathfinding_state = PathfindingState::Running;
-        self.maze = self.maze.from_original();
-        let (sender, receiver): (Sender<PathfindingResult>, Receiver<PathfindingResult>) =
-            channel();
+        self.pathfinding_state = PathfindingState::NotStarted;
 
+        let Some(generator) = self.selected_generator.get_maze_generator() else {
+            return;
+        };
         let maze_type = self.selected_maze_type;
-        let selected_generator = self.selected_generator;
-        let width = self.maze.width;
-        let height = self.maze.height;
-        let handle = thread::spawn(move || {
-            if let Some(mut generator) = selected_generator.get_maze_generator() {
-                generator.generate(
-                    maze_type,
-                    width,
-                    height,
-                    maze_lib::algorithms::Point::default(),
-                    Some(&sender),
-                );
-            }
-        });
+        let (width, height) = match maze_type {
+            MazeType::Polar => (0, MAZE_SIZE / 2),
+            MazeType::Layered(_) => (MAZE_SIZE / 2 + 1, MAZE_SIZE / 2 + 1),
+            _ => (MAZE_SIZE, MAZE_SIZE),
+        };
+
+        // The frames carve the maze out of walls only
+        self.maze = Maze::new(width, height, maze_type, Some(CellType::Wall));
+        self.budget = Budget::new();
+        self.generation = Some(FramePump::carve_within(
+            generator,
+            self.maze.clone(),
+            maze_lib::algorithms::Point::default(),
+            seed,
+            self.budget.clone(),
+        ));
+    }
+
+    /// Rings around the centre cell, ring walls are approximated by short line segments.
+    fn draw_polar(&self, frame: &mut canvas::Frame, bounds: Size) {
+        let side = bounds.width.min(bounds.height);
+        let center = Point::new(side / 2.0, side / 2.0);
+        let ring_height = (side / 2.0 - 1.0) / self.maze.height as f32;
+        let wall_stroke = || {
+            Stroke::default()
+                .with_width(1.0)
+                .with_color(Color::from_rgb8(0, 0, 0))
+        };
+
+        // Points along the circle of `radius` from `start` to `end`, angles grow clockwise
+        let arc = |radius: f32, start: f32, end: f32| -> Vec<Point> {
+            let segments = ((end - start).abs() * radius / 4.0).ceil().max(1.0) as usize;
+            (0..=segments)
+                .map(|i| {
+                    let angle = start + (end - start) * i as f32 / segments as f32;
+                    Point::new(
+                        center.x + radius * angle.cos(),
+                        center.y + radius * angle.sin(),
+                    )
+                })
+                .collect()
+        };
+        let polyline = |points: &[Point], close: bool| {
+            Path::new(|builder| {
+                builder.move_to(points[0]);
+                for &point in &points[1..] {
+                    builder.line_to(point);
+                }
+                if close {
+                    builder.close();
+                }
+            })
+        };
+
+        for y in 0..self.maze.height {
+            let width = self.maze.ring_width(y);
+            let (inner, outer) = (y as f32 * ring_height, (y + 1) as f32 * ring_height);
+            let cell_angle = std::f32::consts::TAU / width as f32;
+
+            for x in 0..width {
+                let cell = self.maze.get_cell(maze_lib::algorithms::Point { x, y });
+                let (start, end) = (x as f32 * cell_angle, (x + 1) as f32 * cell_angle);
+
+                let mut outline = arc(outer, start, end);
+                outline.extend(arc(inner, end, start));
+                frame.fill(&polyline(&outline, true), cell_color(cell));
+                // Keep the terrain visible under the search marks
+                if matches!(cell.get_type(), CellType::Visited | CellType::FinalPath) {
+                    if let Some(color) = terrain_color(cell.get_terrain()) {
+                        let quarter = (end - start) / 4.0;
+                        let middle = (inner + outer) / 2.0;
+                        let mut mark =
+                            arc(middle + ring_height / 4.0, start + quarter, end - quarter);
+                        mark.extend(arc(
+                            middle - ring_height / 4.0,
+                            end - quarter,
+                            start + quarter,
+                        ));
+                        frame.fill(&polyline(&mark, true), color);
+                    }
+                }
 
-        while let Ok(received_result) = receiver.recv() {
-            self.animation_queue.push_back(received_result.maze.clone());
+                if y > 0 && cell.has_polar_wall(PolarDirection::Inward) {
+                    frame.stroke(&polyline(&arc(inner, start, end), false), wall_stroke());
+                }
+                if width > 1 && cell.has_polar_wall(PolarDirection::Clockwise) {
+                    let (from, to) = (arc(inner, end, end)[0], arc(outer, end, end)[0]);
+                    frame.stroke(&Path::line(from, to), wall_stroke());
+                }
+            }
         }
 
-        handle.join().expect("Failed to join thread");
+        // Nothing lies beyond the outermost ring, so it is always closed
+        frame.stroke(
+            &Path::circle(center, self.maze.height as f32 * ring_height),
+            wall_stroke(),
+        );
+    }
 
-        self.grid_cache.clear();
+    /// Pointy-topped hexagons, odd rows are shifted right by half a cell.
+    fn draw_hex(&self, frame: &mut canvas::Frame, bounds: Size) {
+        let sqrt3 = 3f32.sqrt();
+        let (width, height) = (self.maze.width as f32, self.maze.height as f32);
+        let radius =
+            (bounds.width / ((width + 0.5) * sqrt3)).min(bounds.height / (1.5 * height + 0.5));
+
+        // Corners clockwise from the top one, direction `i` is the edge between corners
+        // `i + 1` and `i + 2`
+        let corners = |center: Point, radius: f32| -> [Point; 6] {
+            std::array::from_fn(|k| {
+                let angle = (60.0 * k as f32 - 90.0).to_radians();
+                Point::new(
+                    center.x + radius * angle.cos(),
+                    center.y + radius * angle.sin(),
+                )
+            })
+        };
+        let hexagon = |corners: [Point; 6]| {
+            Path::new(|builder| {
+                builder.move_to(corners[0]);
+                for &corner in &corners[1..] {
+                    builder.line_to(corner);
+                }
+                builder.close();
+            })
+        };
+
+        for y in 0..self.maze.height {
+            for x in 0..self.maze.width {
+                let cell = self.maze.get_cell(maze_lib::algorithms::Point { x, y });
+                let shift = if y % 2 == 1 { 0.5 } else { 0.0 };
+                let center = Point::new(
+                    sqrt3 * radius * (x as f32 + 0.5 + shift),
+                    radius * (1.0 + 1.5 * y as f32),
+                );
+                let outline = corners(center, radius);
+
+                frame.fill(&hexagon(outline), cell_color(cell));
+                // Keep the terrain visible under the search marks
+                if matches!(cell.get_type(), CellType::Visited | CellType::FinalPath) {
+                    if let Some(color) = terrain_color(cell.get_terrain()) {
+                        frame.fill(&hexagon(corners(center, radius / 2.0)), color);
+                    }
+                }
+
+                for (i, direction) in HexDirection::ALL.into_iter().enumerate() {
+                    if cell.has_hex_wall(direction) {
+                        frame.stroke(
+                            &Path::line(outline[(i + 1) % 6], outline[(i + 2) % 6]),
+                            Stroke::default()
+                                .with_width(1.0)
+                                .with_color(Color::from_rgb8(0, 0, 0)),
+                        );
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -217,102 +493,91 @@ impl canvas::Program<Message> for MazeGrid {
         _cursor: mouse::Cursor,
     ) -> Vec<Geometry> {
         let grid = self.grid_cache.draw(renderer, bounds.size(), |frame| {
-            let rows = self.maze.width;
-            let cols = self.maze.height;
+            // Layered mazes show one level at a time, drawn as a slim walls maze
+            let level;
+            let maze = match self.maze.maze_type {
+                MazeType::Layered(_) => {
+                    level = self.maze.level(self.level.min(self.maze.levels() - 1));
+                    &level
+                }
+                _ => &self.maze,
+            };
+            let rows = maze.height;
+            let cols = maze.width;
             let min_bound = min(bounds.width as i32, bounds.height as i32);
             let max_size = max(rows, cols);
             let cell_size: f32 = min_bound as f32 / max_size as f32;
 
-            for col in 0..cols {
-                for row in 0..rows {
-                    let starting_point = Point::new(col as f32 * cell_size, row as f32 * cell_size);
-                    let size = Size::new(cell_size, cell_size);
-                    let cell = self
-                        .maze
-                        .get_cell(maze_lib::algorithms::Point { x: row, y: col });
-                    frame.fill_rectangle(
-                        starting_point,
-                        size,
-                        match cell.get_type() {
-                            CellType::Wall => Color::from_rgb8(100, 100, 100),
-                            CellType::Path => Color::from_rgb8(255, 255, 255),
-                            CellType::Entrance => Color::from_rgb8(0, 0, 255),
-                            CellType::Exit => Color::from_rgb8(255, 0, 0),
-                            CellType::Visited => Color::from_rgb8(0, 0, 100),
-                            CellType::FinalPath => Color::from_rgb8(100, 155, 255),
-                        },
-                    );
-                    match self.maze.maze_type {
-                        MazeType::Thick => {
-                            frame.stroke(
-                                &Path::rectangle(starting_point, size),
-                                Stroke::default()
-                                    .with_width(1.0)
-                                    .with_color(Color::from_rgb8(55, 55, 55)),
-                            );
-                        }
-                        MazeType::Slim => {
-                            if cell.has_right_wall() {
-                                frame.stroke(
-                                    &Path::line(
-                                        Point::new(
-                                            (col + 1) as f32 * cell_size,
-                                            row as f32 * cell_size,
-                                        ),
-                                        Point::new(
-                                            (col + 1) as f32 * cell_size,
-                                            (row + 1) as f32 * cell_size,
-                                        ),
+            if self.maze.maze_type == MazeType::Hex {
+                self.draw_hex(frame, bounds.size());
+            } else if self.maze.maze_type == MazeType::Polar {
+                self.draw_polar(frame, bounds.size());
+            } else {
+                for col in 0..cols {
+                    for row in 0..rows {
+                        let starting_point =
+                            Point::new(col as f32 * cell_size, row as f32 * cell_size);
+                        let size = Size::new(cell_size, cell_size);
+                        let cell = maze.get_cell(maze_lib::algorithms::Point { x: col, y: row });
+                        frame.fill_rectangle(starting_point, size, cell_color(cell));
+                        // Keep the terrain visible under the search marks
+                        if matches!(cell.get_type(), CellType::Visited | CellType::FinalPath) {
+                            if let Some(color) = terrain_color(cell.get_terrain()) {
+                                frame.fill_rectangle(
+                                    Point::new(
+                                        starting_point.x + cell_size / 4.0,
+                                        starting_point.y + cell_size / 4.0,
                                     ),
-                                    Stroke::default()
-                                        .with_width(1.0)
-                                        .with_color(Color::from_rgb8(0, 0, 0)),
+                                    Size::new(cell_size / 2.0, cell_size / 2.0),
+                                    color,
                                 );
                             }
-                            if cell.has_left_wall() {
+                        }
+                        if self.maze.levels() > 1 {
+                            draw_stairs(frame, starting_point, cell_size, cell);
+                        }
+                        match maze.maze_type {
+                            MazeType::Thick => {
                                 frame.stroke(
-                                    &Path::line(
-                                        Point::new(col as f32 * cell_size, row as f32 * cell_size),
-                                        Point::new(
-                                            col as f32 * cell_size,
-                                            (row + 1) as f32 * cell_size,
-                                        ),
-                                    ),
+                                    &Path::rectangle(starting_point, size),
                                     Stroke::default()
                                         .with_width(1.0)
-                                        .with_color(Color::from_rgb8(0, 0, 0)),
+                                        .with_color(Color::from_rgb8(55, 55, 55)),
                                 );
                             }
-                            if cell.has_top_wall() {
-                                frame.stroke(
-                                    &Path::line(
-                                        Point::new(col as f32 * cell_size, row as f32 * cell_size),
-                                        Point::new(
-                                            (col + 1) as f32 * cell_size,
-                                            row as f32 * cell_size,
-                                        ),
-                                    ),
-                                    Stroke::default()
-                                        .with_width(1.0)
-                                        .with_color(Color::from_rgb8(0, 0, 0)),
-                                );
+                            MazeType::Hex | MazeType::Polar | MazeType::Layered(_) => {
+                                unreachable!("Hexagonal and polar cells are drawn separately")
                             }
-                            if cell.has_bottom_wall() {
-                                frame.stroke(
-                                    &Path::line(
-                                        Point::new(
-                                            col as f32 * cell_size,
-                                            (row + 1) as f32 * cell_size,
-                                        ),
-                                        Point::new(
-                                            (col + 1) as f32 * cell_size,
-                                            (row + 1) as f32 * cell_size,
-                                        ),
-                                    ),
-                                    Stroke::default()
-                                        .with_width(1.0)
-                                        .with_color(Color::from_rgb8(0, 0, 0)),
-                                );
+                            MazeType::Slim | MazeType::Toroidal(_) | MazeType::Weave => {
+                                if let MazeType::Toroidal(wrap) = maze.maze_type {
+                                    let joined = [
+                                        wrap.horizontal() && col == 0,
+                                        wrap.horizontal() && col + 1 == cols,
+                                        wrap.vertical() && row == 0,
+                                        wrap.vertical() && row + 1 == rows,
+                                    ];
+                                    draw_wrap_passages(
+                                        frame,
+                                        starting_point,
+                                        cell_size,
+                                        cell,
+                                        joined,
+                                    );
+                                }
+                                // Walls over the ends of a weave crossing's under-passage
+                                // are drawn with a gap, on both sides of the tunnel
+                                let tunnels = [(-1, 0), (1, 0), (0, -1), (0, 1)].map(|d| {
+                                    let (x, y) = (col as i32 + d.0, row as i32 + d.1);
+                                    let next = maze_lib::algorithms::Point {
+                                        x: x as usize,
+                                        y: y as usize,
+                                    };
+                                    maze.maze_type == MazeType::Weave
+                                        && (cell.has_under_passage_along(d)
+                                            || maze.is_valid_coord(x, y)
+                                                && maze.get_cell(next).has_under_passage_along(d))
+                                });
+                                draw_slim_walls(frame, starting_point, cell_size, cell, tunnels);
                             }
                         }
                     }
@@ -323,7 +588,7 @@ impl canvas::Program<Message> for MazeGrid {
                 frame.stroke(
                     &Path::rectangle(
                         Point::new(0.0, 0.0),
-                        Size::new(cell_size * rows as f32, cell_size * cols as f32 - 1.0),
+                        Size::new(cell_size * cols as f32, cell_size * rows as f32 - 1.0),
                     ),
                     Stroke::default()
                         .with_width(1.0)
@@ -335,7 +600,7 @@ impl canvas::Program<Message> for MazeGrid {
                 frame.stroke(
                     &Path::rectangle(
                         Point::new(0.0, 0.0),
-                        Size::new(cell_size * rows as f32, cell_size * cols as f32 - 1.0),
+                        Size::new(cell_size * cols as f32, cell_size * rows as f32 - 1.0),
                     ),
                     Stroke::default()
                         .with_width(1.0)
@@ -348,12 +613,130 @@ impl canvas::Program<Message> for MazeGrid {
     }
 }
 
-pub enum Interaction {
-    None,
+/// Triangle pointing up in the upper half of a cell with stairs up, and one pointing down
+/// in the lower half for stairs down.
+fn draw_stairs(frame: &mut canvas::Frame, top_left: Point, cell_size: f32, cell: MazeCell) {
+    let color = Color::from_rgb8(200, 120, 0);
+    let (left, right) = (top_left.x + cell_size / 4.0, top_left.x + cell_size * 0.75);
+    let middle = top_left.x + cell_size / 2.0;
+    let triangle = |tip: Point, base: f32| {
+        Path::new(|builder| {
+            builder.move_to(tip);
+            builder.line_to(Point::new(right, base));
+            builder.line_to(Point::new(left, base));
+            builder.close();
+        })
+    };
+
+    if !cell.has_up_wall() {
+        let tip = Point::new(middle, top_left.y + cell_size / 8.0);
+        frame.fill(&triangle(tip, top_left.y + cell_size * 0.45), color);
+    }
+    if !cell.has_down_wall() {
+        let tip = Point::new(middle, top_left.y + cell_size * 7.0 / 8.0);
+        frame.fill(&triangle(tip, top_left.y + cell_size * 0.55), color);
+    }
+}
+
+/// Highlights the gaps in the outer wall of a toroidal maze, the passages there lead over
+/// to the opposite edge. `joined` tells which of the left, right, top and bottom sides of
+/// the cell lie on a joined edge.
+fn draw_wrap_passages(
+    frame: &mut canvas::Frame,
+    top_left: Point,
+    cell_size: f32,
+    cell: MazeCell,
+    joined: [bool; 4],
+) {
+    let (left, top) = (top_left.x, top_left.y);
+    let (right, bottom) = (left + cell_size, top + cell_size);
+    let sides = [
+        (cell.has_left_wall(), (left, top), (left, bottom)),
+        (cell.has_right_wall(), (right, top), (right, bottom)),
+        (cell.has_top_wall(), (left, top), (right, top)),
+        (cell.has_bottom_wall(), (left, bottom), (right, bottom)),
+    ];
+
+    for (is_joined, (has_wall, from, to)) in joined.into_iter().zip(sides) {
+        if is_joined && !has_wall {
+            frame.stroke(
+                &Path::line(Point::new(from.0, from.1), Point::new(to.0, to.1)),
+                Stroke::default()
+                    .with_width(3.0)
+                    .with_color(Color::from_rgb8(200, 120, 0)),
+            );
+        }
+    }
 }
 
-impl Default for Interaction {
-    fn default() -> Self {
-        Self::None
+/// Walls on the left, right, top and bottom sides of a slim cell. A side marked in `tunnels`
+/// is only drawn at its corners, the under-passage of a weave crossing runs through it.
+fn draw_slim_walls(
+    frame: &mut canvas::Frame,
+    top_left: Point,
+    cell_size: f32,
+    cell: MazeCell,
+    tunnels: [bool; 4],
+) {
+    let (left, top) = (top_left.x, top_left.y);
+    let (right, bottom) = (left + cell_size, top + cell_size);
+    let sides = [
+        (cell.has_left_wall(), (left, top), (left, bottom)),
+        (cell.has_right_wall(), (right, top), (right, bottom)),
+        (cell.has_top_wall(), (left, top), (right, top)),
+        (cell.has_bottom_wall(), (left, bottom), (right, bottom)),
+    ];
+
+    for (is_tunnel, (has_wall, from, to)) in tunnels.into_iter().zip(sides) {
+        if !has_wall {
+            continue;
+        }
+        let (from, to) = (Point::new(from.0, from.1), Point::new(to.0, to.1));
+        let path = if is_tunnel {
+            let along =
+                |t: f32| Point::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+            Path::new(|builder| {
+                builder.move_to(from);
+                builder.line_to(along(0.25));
+                builder.move_to(along(0.75));
+                builder.line_to(to);
+            })
+        } else {
+            Path::line(from, to)
+        };
+        frame.stroke(
+            &path,
+            Stroke::default()
+                .with_width(1.0)
+                .with_color(Color::from_rgb8(0, 0, 0)),
+        );
     }
 }
+
+fn cell_color(cell: MazeCell) -> Color {
+    match cell.get_type() {
+        CellType::Wall => Color::from_rgb8(100, 100, 100),
+        CellType::Path => {
+            terrain_color(cell.get_terrain()).unwrap_or(Color::from_rgb8(255, 255, 255))
+        }
+        CellType::Entrance => Color::from_rgb8(0, 0, 255),
+        CellType::Exit => Color::from_rgb8(255, 0, 0),
+        CellType::Visited => Color::from_rgb8(0, 0, 100),
+        CellType::FinalPath => Color::from_rgb8(100, 155, 255),
+    }
+}
+
+fn terrain_color(terrain: Terrain) -> Option<Color> {
+    match terrain {
+        Terrain::Normal => None,
+        Terrain::SwampLite => Some(Color::from_rgb8(190, 200, 140)),
+        Terrain::SwampMedium => Some(Color::from_rgb8(140, 160, 90)),
+        Terrain::SwampHeavy => Some(Color::from_rgb8(90, 110, 50)),
+    }
+}
+
+#[derive(Default)]
+pub enum Interaction {
+    #[default]
+    None,
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, maze_cells, send_frame, walk_frame_interval,
+    FrameSink, MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Aldous-Broder algorithm: a single random walk that carves a passage whenever it enters
+/// a cell for the first time. Produces a uniform spanning tree, but needs a lot of steps.
+#[derive(Default)]
+pub struct AldousBroder;
+
+impl AldousBroder {
+    pub fn new() -> Self {
+        AldousBroder
+    }
+}
+
+impl MazeGenerationAlgorithm for AldousBroder {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let cell_count = maze_cells(&maze, entrance).len();
+        let frame_interval = walk_frame_interval(cell_count);
+
+        let mut in_maze = HashSet::from([entrance]);
+        maze.mark_cell_as_path(entrance);
+
+        let mut current = entrance;
+        let mut steps = 0;
+        while in_maze.len() < cell_count {
+            let next = *cell_neighbors(&maze, current)
+                .choose(&mut rng)
+                .ok_or(PathfindingError::InvalidMaze)?;
+
+            let carved = in_maze.insert(next);
+            if carved {
+                carve_passage(&mut maze, current, next);
+            }
+            current = next;
+
+            steps += 1;
+            if carved || steps % frame_interval == 0 {
+                // Show where the walker is
+                maze.mark_cell_as_visited(current);
+                send_frame(&mut maze, sender)?;
+                maze.mark_cell_as_path(current);
+            }
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
@@ -1,40 +1,196 @@
 use crate::MazeType;
 
+use super::AStar;
+use super::AldousBroder;
 use super::Backtracking;
+use super::BellmanFord;
+use super::BinaryTree;
+use super::Cave;
+use super::CaveStyle;
+use super::Dijkstra;
+use super::Dungeon;
+use super::Eller;
+use super::GrowingTree;
+use super::HuntAndKill;
+use super::Kruskal;
 use super::MazeGenerationAlgorithm;
+use super::PathfindingAlgorithm;
+use super::Prim;
+use super::RecursiveDivision;
+use super::SelectionPolicy;
+use super::Sidewinder;
+use super::Wilson;
+use super::BFS;
 use super::DFS;
+use super::WFC;
 use enum_iterator::Sequence;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Sequence)]
 pub enum Algorithm {
+    AldousBroder,
     AStar,
     #[default]
     Backtracking,
     BellmanFord,
     BFS,
+    BinaryTree,
+    Cave(CaveStyle),
     DFS,
     Dijkstra,
+    Dungeon,
+    Eller,
+    GrowingTree(SelectionPolicy),
+    HuntAndKill,
+    Kruskal,
+    Prim,
+    RecursiveDivision,
+    Sidewinder,
     WFC,
+    Wilson,
 }
 
 impl Algorithm {
-    pub const ALL: [Algorithm; 7] = [
+    pub const ALL: [Algorithm; 26] = [
+        Algorithm::AldousBroder,
         Algorithm::AStar,
         Algorithm::Backtracking,
         Algorithm::BellmanFord,
         Algorithm::BFS,
+        Algorithm::BinaryTree,
+        Algorithm::Cave(CaveStyle::Winding),
+        Algorithm::Cave(CaveStyle::Open),
+        Algorithm::Cave(CaveStyle::Rough),
         Algorithm::DFS,
         Algorithm::Dijkstra,
+        Algorithm::Dungeon,
+        Algorithm::Eller,
+        Algorithm::GrowingTree(SelectionPolicy::Newest),
+        Algorithm::GrowingTree(SelectionPolicy::Random),
+        Algorithm::GrowingTree(SelectionPolicy::Oldest),
+        Algorithm::GrowingTree(SelectionPolicy::MostlyNewest),
+        Algorithm::GrowingTree(SelectionPolicy::Mixed),
+        Algorithm::GrowingTree(SelectionPolicy::MostlyRandom),
+        Algorithm::HuntAndKill,
+        Algorithm::Kruskal,
+        Algorithm::Prim,
+        Algorithm::RecursiveDivision,
+        Algorithm::Sidewinder,
         Algorithm::WFC,
+        Algorithm::Wilson,
     ];
 
     pub fn maze_generation_algorithms(maze_type: MazeType) -> Vec<Algorithm> {
         match maze_type {
-            MazeType::Thick => vec![Algorithm::DFS, Algorithm::Backtracking],
-            MazeType::Slim => vec![Algorithm::Backtracking],
+            MazeType::Thick => vec![
+                Algorithm::DFS,
+                Algorithm::Backtracking,
+                Algorithm::Kruskal,
+                Algorithm::Prim,
+                Algorithm::GrowingTree(SelectionPolicy::Newest),
+                Algorithm::GrowingTree(SelectionPolicy::Random),
+                Algorithm::GrowingTree(SelectionPolicy::Oldest),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyNewest),
+                Algorithm::GrowingTree(SelectionPolicy::Mixed),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyRandom),
+                Algorithm::Wilson,
+                Algorithm::AldousBroder,
+                Algorithm::Eller,
+                Algorithm::HuntAndKill,
+                Algorithm::BinaryTree,
+                Algorithm::Sidewinder,
+                Algorithm::RecursiveDivision,
+                Algorithm::WFC,
+                Algorithm::Cave(CaveStyle::Winding),
+                Algorithm::Cave(CaveStyle::Open),
+                Algorithm::Cave(CaveStyle::Rough),
+                Algorithm::Dungeon,
+            ],
+            MazeType::Slim => vec![
+                Algorithm::Backtracking,
+                Algorithm::Kruskal,
+                Algorithm::Prim,
+                Algorithm::GrowingTree(SelectionPolicy::Newest),
+                Algorithm::GrowingTree(SelectionPolicy::Random),
+                Algorithm::GrowingTree(SelectionPolicy::Oldest),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyNewest),
+                Algorithm::GrowingTree(SelectionPolicy::Mixed),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyRandom),
+                Algorithm::Wilson,
+                Algorithm::AldousBroder,
+                Algorithm::Eller,
+                Algorithm::HuntAndKill,
+                Algorithm::BinaryTree,
+                Algorithm::Sidewinder,
+                Algorithm::RecursiveDivision,
+                Algorithm::WFC,
+            ],
+            MazeType::Hex => vec![
+                Algorithm::Backtracking,
+                Algorithm::Kruskal,
+                Algorithm::Prim,
+                Algorithm::GrowingTree(SelectionPolicy::Newest),
+                Algorithm::GrowingTree(SelectionPolicy::Random),
+                Algorithm::GrowingTree(SelectionPolicy::Oldest),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyNewest),
+                Algorithm::GrowingTree(SelectionPolicy::Mixed),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyRandom),
+                Algorithm::Wilson,
+                Algorithm::AldousBroder,
+                Algorithm::Eller,
+                Algorithm::HuntAndKill,
+                Algorithm::BinaryTree,
+                Algorithm::Sidewinder,
+                Algorithm::WFC,
+            ],
+            // Only generators that carve from cells already in the maze can tunnel beneath them
+            MazeType::Weave => vec![
+                Algorithm::Backtracking,
+                Algorithm::GrowingTree(SelectionPolicy::Newest),
+                Algorithm::GrowingTree(SelectionPolicy::Random),
+                Algorithm::GrowingTree(SelectionPolicy::Oldest),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyNewest),
+                Algorithm::GrowingTree(SelectionPolicy::Mixed),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyRandom),
+                Algorithm::HuntAndKill,
+            ],
+            MazeType::Polar | MazeType::Layered(_) | MazeType::Toroidal(_) => vec![
+                Algorithm::Backtracking,
+                Algorithm::Kruskal,
+                Algorithm::Prim,
+                Algorithm::GrowingTree(SelectionPolicy::Newest),
+                Algorithm::GrowingTree(SelectionPolicy::Random),
+                Algorithm::GrowingTree(SelectionPolicy::Oldest),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyNewest),
+                Algorithm::GrowingTree(SelectionPolicy::Mixed),
+                Algorithm::GrowingTree(SelectionPolicy::MostlyRandom),
+                Algorithm::Wilson,
+                Algorithm::AldousBroder,
+                Algorithm::HuntAndKill,
+            ],
         }
     }
 
+    /// Generators that can carve a maze in the shape of a mask, see
+    /// `MazeGenerationAlgorithm::generate_masked`. Row by row generators and those filling
+    /// the whole rectangle are left out.
+    pub fn masked_generation_algorithms(maze_type: MazeType) -> Vec<Algorithm> {
+        Algorithm::maze_generation_algorithms(maze_type)
+            .into_iter()
+            .filter(|algorithm| {
+                !matches!(
+                    algorithm,
+                    Algorithm::Eller
+                        | Algorithm::BinaryTree
+                        | Algorithm::Sidewinder
+                        | Algorithm::RecursiveDivision
+                        | Algorithm::WFC
+                        | Algorithm::Cave(_)
+                        | Algorithm::Dungeon
+                )
+            })
+            .collect()
+    }
+
     pub fn pathfinding_algorithms() -> Vec<Algorithm> {
         vec![
             Algorithm::AStar,
@@ -50,11 +206,35 @@ impl Algorithm {
         Self::pathfinding_algorithms().contains(self)
     }
 
-    pub fn get_maze_generator(&self) -> Option<Box<dyn MazeGenerationAlgorithm>> {
+    pub fn get_maze_generator(&self) -> Option<Box<dyn MazeGenerationAlgorithm + Send>> {
         match self {
             Algorithm::DFS => Some(Box::new(DFS::default())),
             Algorithm::Backtracking => Some(Box::new(Backtracking::default())),
-            // Algorithm::WFC => Some(Box::new(WFC::default())),
+            Algorithm::Kruskal => Some(Box::new(Kruskal::new())),
+            Algorithm::Prim => Some(Box::new(Prim::new())),
+            Algorithm::GrowingTree(policy) => Some(Box::new(GrowingTree::new(*policy))),
+            Algorithm::Wilson => Some(Box::new(Wilson::new())),
+            Algorithm::AldousBroder => Some(Box::new(AldousBroder::new())),
+            Algorithm::Eller => Some(Box::new(Eller::new())),
+            Algorithm::RecursiveDivision => Some(Box::new(RecursiveDivision::new())),
+            Algorithm::HuntAndKill => Some(Box::new(HuntAndKill::new())),
+            Algorithm::BinaryTree => Some(Box::new(BinaryTree::new())),
+            Algorithm::Sidewinder => Some(Box::new(Sidewinder::new())),
+            Algorithm::WFC => Some(Box::new(WFC::new())),
+            Algorithm::Cave(style) => Some(Box::new(Cave::with_style(*style))),
+            Algorithm::Dungeon => Some(Box::new(Dungeon::default())),
+            _ => None,
+        }
+    }
+
+    pub fn get_pathfinder(&self) -> Option<Box<dyn PathfindingAlgorithm + Send>> {
+        match self {
+            Algorithm::AStar => Some(Box::new(AStar::new())),
+            Algorithm::Backtracking => Some(Box::new(Backtracking::new())),
+            Algorithm::BellmanFord => Some(Box::new(BellmanFord)),
+            Algorithm::BFS => Some(Box::new(BFS::new())),
+            Algorithm::DFS => Some(Box::new(DFS::new())),
+            Algorithm::Dijkstra => Some(Box::new(Dijkstra::new())),
             _ => None,
         }
     }
@@ -66,13 +246,29 @@ impl std::fmt::Display for Algorithm {
             f,
             "{}",
             match self {
+                Algorithm::AldousBroder => "Aldous-Broder",
                 Algorithm::AStar => "AStar",
                 Algorithm::Backtracking => "Backtracking",
                 Algorithm::BellmanFord => "Bellman-Ford",
                 Algorithm::BFS => "Breadth-First Search (BFS)",
+                Algorithm::BinaryTree => "Binary Tree",
+                Algorithm::Cave(style) => {
+                    return write!(f, "Cellular Automaton Cave ({})", style);
+                }
                 Algorithm::DFS => "Depth-First Search (DFS)",
                 Algorithm::Dijkstra => "Dijkstra's",
+                Algorithm::Dungeon => "Dungeon",
+                Algorithm::Eller => "Eller's",
+                Algorithm::GrowingTree(policy) => {
+                    return write!(f, "Growing Tree ({})", policy);
+                }
+                Algorithm::HuntAndKill => "Hunt-and-Kill",
+                Algorithm::Kruskal => "Kruskal's",
+                Algorithm::Prim => "Prim's",
+                Algorithm::RecursiveDivision => "Recursive Division",
+                Algorithm::Sidewinder => "Sidewinder",
                 Algorithm::WFC => "Wave Function Collapse",
+                Algorithm::Wilson => "Wilson's",
             }
         )
     }
@@ -1,12 +1,14 @@
 use std::{
     cmp::Ordering,
     collections::{BinaryHeap, HashMap},
-    sync::mpsc::Sender,
 };
 
 use crate::maze::Maze;
 
-use super::{pathfinding::PathfindingAlgorithm, Algorithm, Movements, PathfindingResult, Point};
+use super::{
+    endpoints, pathfinding::PathfindingAlgorithm, send_final_path, send_step, Algorithm, FrameSink,
+    PathfindingError, PathfindingOutcome, Point, StepEvent,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 struct Node {
@@ -36,9 +38,10 @@ impl AStar {
         AStar {}
     }
 
-    fn heuristic(&self, current: &Point, goal: &Point) -> u32 {
-        // Simple Manhattan distance as the heuristic
-        ((current.x as i32 - goal.x as i32).abs() + (current.y as i32 - goal.y as i32).abs()) as u32
+    fn heuristic(&self, maze: &Maze, current: &Point, goal: &Point) -> u32 {
+        // Fewest moves ignoring walls, Manhattan distance on square grids. It stays
+        // admissible on weighted terrain because no cell costs less than 1 to enter
+        maze.distance(*current, *goal) as u32
     }
 
     fn reconstruct_path(
@@ -58,10 +61,13 @@ impl AStar {
 }
 
 impl PathfindingAlgorithm for AStar {
-    fn find_path(&mut self, maze: &mut Maze, sender: &Sender<PathfindingResult>) {
+    fn find_path(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
         // Find entrance and exit coordinates
-        let entrance = maze.get_entrance().unwrap();
-        let exit = maze.get_exit().unwrap();
+        let (entrance, exit) = endpoints(maze)?;
 
         let start = entrance;
         let goal = exit;
@@ -73,48 +79,36 @@ impl PathfindingAlgorithm for AStar {
         open_set.push(Node {
             point: start,
             g: 0,
-            h: self.heuristic(&start, &goal),
+            h: self.heuristic(maze, &start, &goal),
         });
         g_scores.insert(start, 0);
 
+        let mut expanded = 0;
+        let mut frontier = vec![StepEvent::FrontierPush(start)];
         while let Some(current_node) = open_set.pop() {
             let current = current_node.point;
+            // Skip stale queue entries, a cheaper route to this cell was already expanded
+            if current_node.g > g_scores[&current] {
+                continue;
+            }
+            sender.expand()?;
+            expanded += 1;
             maze.mark_cell_as_visited(current);
-            sender
-                .send(PathfindingResult {
-                    maze: maze.clone(),
-                    stats: self.get_stats(),
-                })
-                .unwrap();
+            frontier.push(StepEvent::FrontierPop(current));
+            send_step(maze, sender, std::mem::take(&mut frontier))?;
 
             if current == goal {
                 let path = AStar::reconstruct_path(&came_from, current);
-                for point in path.iter().skip(1) {
-                    maze.mark_cell_as_final_path(*point);
-
-                    sender
-                        .send(PathfindingResult {
-                            maze: maze.clone(),
-                            stats: None,
-                        })
-                        .expect("Failed to send maze to the main thread");
-                }
-                break;
+                send_final_path(maze, sender, &path)?;
+                return Ok(PathfindingOutcome::found(maze, path, expanded));
             }
 
-            for (dx, dy) in &Movements::directions() {
-                let neighbor = Point {
-                    x: (current.x as i32 + dx) as usize,
-                    y: (current.y as i32 + dy) as usize,
-                };
-
-                if !maze.is_valid_coord(neighbor.x as i32, neighbor.y as i32)
-                    || maze.is_not_passable(current, neighbor)
-                {
+            for neighbor in maze.neighbors(current) {
+                if maze.is_not_passable(current, neighbor) {
                     continue;
                 }
 
-                let tentative_g_score = g_scores[&current] + 1;
+                let tentative_g_score = g_scores[&current] + maze.get_cost(neighbor);
 
                 if !g_scores.contains_key(&neighbor) || tentative_g_score < g_scores[&neighbor] {
                     g_scores.insert(neighbor, tentative_g_score);
@@ -123,11 +117,14 @@ impl PathfindingAlgorithm for AStar {
                     open_set.push(Node {
                         point: neighbor,
                         g: tentative_g_score,
-                        h: self.heuristic(&neighbor, &goal),
+                        h: self.heuristic(maze, &neighbor, &goal),
                     });
+                    frontier.push(StepEvent::FrontierPush(neighbor));
                 }
             }
         }
+
+        Ok(PathfindingOutcome::unreachable(expanded))
     }
 
     fn name(&self) -> Algorithm {
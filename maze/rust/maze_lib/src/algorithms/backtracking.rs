@@ -1,103 +1,107 @@
 use std::collections::HashSet;
-use std::sync::mpsc::Sender;
 
-use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
-use crate::{maze::Maze, CellType, MazeType};
+use crate::{maze::Maze, CellType};
 
 use super::{
-    pathfinding::PathfindingAlgorithm, Algorithm, MazeGenerationAlgorithm, Movements,
-    PathfindingResult, PathfindingStats, Point,
+    carve_passage, cell_neighbors, endpoints, pathfinding::PathfindingAlgorithm, send_frame,
+    send_step, Algorithm, FrameSink, MazeGenerationAlgorithm, PathfindingError, PathfindingOutcome,
+    PathfindingStats, Point, StepEvent,
 };
 
 #[derive(Default)]
 pub struct Backtracking {
     stats: PathfindingStats,
+    /// Pushes and pops of the path that are not sent yet
+    frontier: Vec<StepEvent>,
 }
 
 impl Backtracking {
     pub fn new() -> Self {
         Backtracking {
             stats: PathfindingStats::default(),
+            frontier: Vec::new(),
         }
     }
 
     fn backtrack(
         &mut self,
         maze: &mut Maze,
-        sender: &Sender<PathfindingResult>,
+        sender: &dyn FrameSink,
         current: Point,
         exit: Point,
-    ) -> bool {
+        path: &mut Vec<Point>,
+    ) -> Result<bool, PathfindingError> {
+        sender.expand()?;
         maze.mark_cell_as_visited(current);
+        path.push(current);
+        self.frontier.push(StepEvent::FrontierPush(current));
 
         // Update stats
         self.stats.new_step();
 
         // If we've reached the exit, stop recursion
         if current == exit {
-            sender
-                .send(PathfindingResult {
-                    maze: maze.clone(),
-                    stats: Some(self.stats),
-                })
-                .unwrap();
-            return true;
+            return Ok(true);
         }
 
-        let directions = &Movements::directions();
-        let mut rng = rand::thread_rng();
-        let shuffled_directions = directions.choose_multiple(&mut rng, directions.len());
-
-        for &(dx, dy) in shuffled_directions {
-            let new_x: i32 = current.x as i32 + dx;
-            let new_y: i32 = current.y as i32 + dy;
-            let neighbor = Point {
-                x: new_x as usize,
-                y: new_y as usize,
-            };
-
-            if maze.is_valid_coord(new_x, new_y)
-                && (maze.is_passable(current, neighbor) || neighbor == exit)
-            {
-                sender
-                    .send(PathfindingResult {
-                        maze: maze.clone(),
-                        stats: Some(self.stats),
-                    })
-                    .unwrap();
+        let mut neighbors = maze.neighbors(current);
+        neighbors.shuffle(&mut rand::thread_rng());
+
+        for neighbor in neighbors {
+            // Cells on the current path or given up on before are not entered again
+            let is_seen = matches!(
+                maze.get_cell(neighbor).get_type(),
+                CellType::Visited | CellType::FinalPath
+            );
+            if !is_seen && maze.is_passable(current, neighbor) {
+                let mut events = std::mem::take(&mut self.frontier);
+                events.push(StepEvent::Stats(self.stats));
+                send_step(maze, sender, events)?;
 
                 // Mark the final path
                 maze.mark_cell_as_final_path(current);
                 // Mark the path recursively backtrack
-                if self.backtrack(
-                    maze,
-                    sender,
-                    Point {
-                        x: new_x as usize,
-                        y: new_y as usize,
-                    },
-                    exit,
-                ) {
-                    return true;
+                if self.backtrack(maze, sender, neighbor, exit, path)? {
+                    return Ok(true);
                 } else {
                     maze.mark_cell_as_visited(current);
                 }
             }
         }
 
-        false
+        path.pop();
+        self.frontier.push(StepEvent::FrontierPop(current));
+        Ok(false)
     }
 }
 
 impl PathfindingAlgorithm for Backtracking {
-    fn find_path(&mut self, maze: &mut Maze, sender: &Sender<PathfindingResult>) {
-        // Reset the maze to its original state
+    fn find_path(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
         // Find entrance and exit coordinated
-        let entrance = maze.get_entrance().expect("Cannot find entrance");
-        let exit = maze.get_exit().expect("Cannot find exit");
+        let (entrance, exit) = endpoints(maze)?;
+
+        self.stats = PathfindingStats::default();
+        self.frontier.clear();
+        let mut path = Vec::new();
+        let found = self.backtrack(maze, sender, entrance, exit, &mut path)?;
 
-        self.backtrack(maze, sender, entrance, exit);
+        let mut events = std::mem::take(&mut self.frontier);
+        events.push(StepEvent::Stats(self.stats));
+        if found {
+            events.push(StepEvent::PathFound(path.clone()));
+        }
+        send_step(maze, sender, events)?;
+        if found {
+            Ok(PathfindingOutcome::found(maze, path, self.stats.steps))
+        } else {
+            Ok(PathfindingOutcome::unreachable(self.stats.steps))
+        }
     }
 
     fn name(&self) -> Algorithm {
@@ -110,16 +114,14 @@ impl PathfindingAlgorithm for Backtracking {
 }
 
 impl MazeGenerationAlgorithm for Backtracking {
-    fn generate(
+    fn carve(
         &mut self,
-        maze_type: MazeType,
-        width: usize,
-        height: usize,
+        mut maze: Maze,
         entrance: Point,
-        sender: Option<&Sender<PathfindingResult>>,
-    ) -> Option<Maze> {
-        let mut maze = Maze::new(width, height, maze_type, Some(CellType::Wall));
-        let mut rng = rand::thread_rng();
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
         let mut visited = HashSet::new();
 
         fn generate_maze_recursive(
@@ -127,65 +129,36 @@ impl MazeGenerationAlgorithm for Backtracking {
             maze: &mut Maze,
             visited: &mut HashSet<Point>,
             rng: &mut impl rand::Rng,
-            sender: Option<&Sender<PathfindingResult>>,
-        ) {
+            sender: Option<&dyn FrameSink>,
+        ) -> Result<(), PathfindingError> {
             visited.insert(current);
             maze.mark_cell_as_path(current);
 
-            let mut directions = match maze.maze_type {
-                MazeType::Thick => Movements::directions_doubled().to_vec(),
-                MazeType::Slim => Movements::directions().to_vec(),
-            };
-            directions.shuffle(rng);
-
-            for (dx, dy) in directions {
-                let new_x = current.x as i32 + dx;
-                let new_y = current.y as i32 + dy;
-
-                if maze.is_valid_coord(new_x, new_y) {
-                    let next = Point {
-                        x: new_x as usize,
-                        y: new_y as usize,
-                    };
-
-                    if !visited.contains(&next) {
-                        match maze.maze_type {
-                            MazeType::Thick => maze.mark_cell_as_path(Point {
-                                x: (current.x + next.x) / 2,
-                                y: (current.y + next.y) / 2,
-                            }),
-
-                            MazeType::Slim => maze.remove_walls_between_cells(current, next),
-                        }
-                        if let Some(s) = sender {
-                            s.send(PathfindingResult {
-                                stats: None,
-                                maze: maze.clone(),
-                            })
-                            .unwrap();
-                        }
-                        generate_maze_recursive(next, maze, visited, rng, sender);
-                    }
+            let mut neighbors = cell_neighbors(maze, current);
+            neighbors.shuffle(rng);
+
+            for next in neighbors {
+                if !visited.contains(&next) {
+                    carve_passage(maze, current, next);
+                    send_frame(maze, sender)?;
+                    generate_maze_recursive(next, maze, visited, rng, sender)?;
                 }
             }
+            Ok(())
         }
 
-        generate_maze_recursive(entrance, &mut maze, &mut visited, &mut rng, sender);
+        generate_maze_recursive(entrance, &mut maze, &mut visited, &mut rng, sender)?;
 
         // Set entrance and exit
         maze.mark_cell_as_entrance(entrance);
-        let exit_point = maze.get_random_boundary_point(&mut rng);
+        let exit_point = maze
+            .get_random_boundary_point(&mut rng)
+            .ok_or(PathfindingError::InvalidMaze)?;
         maze.mark_cell_as_exit(exit_point);
 
-        if let Some(s) = sender {
-            s.send(PathfindingResult {
-                stats: None,
-                maze: maze.clone(),
-            })
-            .unwrap();
-        }
+        send_frame(&mut maze, sender)?;
 
         maze.backup();
-        Some(maze)
+        Ok(maze)
     }
 }
@@ -1,43 +1,42 @@
-use std::{collections::HashMap, sync::mpsc::Sender};
+use std::collections::HashMap;
 
 use crate::Maze;
 
-use super::{Movements, PathfindingAlgorithm, PathfindingResult, Point};
+use super::{
+    endpoints, send_final_path, send_step, FrameSink, PathfindingAlgorithm, PathfindingError,
+    PathfindingOutcome, Point, StepEvent,
+};
 
 #[derive(Default)]
 pub struct BellmanFord;
 
 impl BellmanFord {
+    /// Relaxes the edge leading into `neighbor`, returns `true` if its distance improved.
     fn relax_edges(
         current: Point,
         neighbor: Point,
-        goal: Point,
         maze: &mut Maze,
         distance: &mut HashMap<Point, i32>,
         predecessor: &mut HashMap<Point, Point>,
-        sender: &Sender<PathfindingResult>,
-        optimal_route_found: &mut bool,
-    ) {
-        let weight = 1; // Assuming uniform edge weights
+        sender: &dyn FrameSink,
+    ) -> Result<bool, PathfindingError> {
+        // Entering a cell costs as much as its terrain
+        let weight = maze.get_cost(neighbor) as i32;
         let tentative_distance = distance[&current].saturating_add(weight);
 
         if tentative_distance < distance[&neighbor] {
             distance.insert(neighbor, tentative_distance);
             predecessor.insert(neighbor, current);
 
-            // Visualize the update by marking the cell as Visited ans sending the updated maze
+            // Visualize the update by marking the cell as Visited ans sending the change, a
+            // cell whose distance improved is pushed to be relaxed from again
             maze.mark_cell_as_visited(neighbor);
-            sender
-                .send(PathfindingResult {
-                    stats: None,
-                    maze: maze.clone(),
-                })
-                .expect("Failed to send maze to the main thread");
-
-            if neighbor == goal {
-                *optimal_route_found = true;
-            }
+            send_step(maze, sender, vec![StepEvent::FrontierPush(neighbor)])?;
+
+            return Ok(true);
         }
+
+        Ok(false)
     }
 
     fn reconstruct_path(
@@ -46,8 +45,8 @@ impl BellmanFord {
         goal: Point,
         maze: &mut Maze,
         predecessor: &mut HashMap<Point, Point>,
-        sender: &Sender<PathfindingResult>,
-    ) {
+        sender: &dyn FrameSink,
+    ) -> Result<Vec<Point>, PathfindingError> {
         // Reconstruct the path
         let mut current = goal;
         let mut path = Vec::new();
@@ -60,23 +59,18 @@ impl BellmanFord {
         path.push(start);
         path.reverse();
 
-        for point in path.iter().skip(1) {
-            maze.mark_cell_as_final_path(*point);
-
-            sender
-                .send(PathfindingResult {
-                    stats: None,
-                    maze: maze.clone(),
-                })
-                .expect("Failed to send pathfinding result");
-        }
+        send_final_path(maze, sender, &path)?;
+        Ok(path)
     }
 }
 
 impl PathfindingAlgorithm for BellmanFord {
-    fn find_path(&mut self, maze: &mut Maze, sender: &Sender<PathfindingResult>) {
-        let entrance = maze.get_entrance().expect("Entrance not found");
-        let exit = maze.get_exit().expect("Exit not found");
+    fn find_path(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
+        let (entrance, exit) = endpoints(maze)?;
 
         let mut distance: HashMap<Point, i32> = HashMap::new();
         let mut predecessor: HashMap<Point, Point> = HashMap::new();
@@ -90,72 +84,49 @@ impl PathfindingAlgorithm for BellmanFord {
 
         distance.insert(entrance, 0);
 
-        let mut optimal_route_found = false;
-
+        let mut expanded = 0;
         for _ in 0..(maze.width * maze.height) - 1 {
+            let mut distance_updated = false;
+
             for y in 0..maze.height {
                 for x in 0..maze.width {
                     let current = Point { x, y };
+                    if distance[&current] == i32::MAX {
+                        continue;
+                    }
+                    // Checked for every cell, a pass may improve nothing and send no frame
+                    sender.expand()?;
+                    expanded += 1;
 
-                    for (dx, dy) in &Movements::directions() {
-                        let neighbor = Point {
-                            x: (x as i32 + dx) as usize,
-                            y: (y as i32 + dy) as usize,
-                        };
-
-                        if !maze.is_valid_coord(neighbor.x as i32, neighbor.y as i32)
-                            || maze.is_not_passable(current, neighbor)
-                        {
+                    for neighbor in maze.neighbors(current) {
+                        if maze.is_not_passable(current, neighbor) {
                             continue;
                         }
 
-                        BellmanFord::relax_edges(
+                        distance_updated |= BellmanFord::relax_edges(
                             current,
                             neighbor,
-                            exit,
                             maze,
                             &mut distance,
                             &mut predecessor,
                             sender,
-                            &mut optimal_route_found,
-                        );
-
-                        if optimal_route_found {
-                            self.reconstruct_path(entrance, exit, maze, &mut predecessor, sender);
-                            return;
-                        }
+                        )?;
                     }
                 }
             }
-        }
 
-        // Check for negative cycles
-        for y in 0..maze.height {
-            for x in 0..maze.width {
-                let current = Point { x, y };
-
-                for (dx, dy) in &Movements::directions() {
-                    let neighbor = Point {
-                        x: (current.x as i32 + dx) as usize,
-                        y: (current.y as i32 + dy) as usize,
-                    };
-
-                    if !maze.is_valid_coord(neighbor.x as i32, neighbor.y as i32)
-                        || maze.is_not_passable(current, neighbor)
-                    {
-                        continue;
-                    }
-
-                    let weight = 1;
-
-                    let tentative_distance = distance[&current].saturating_add(weight);
-
-                    if tentative_distance < distance[&neighbor] {
-                        return;
-                    }
-                }
+            // Terrain costs are always positive, so there are no negative cycles and
+            // the distances are final as soon as a whole pass changes nothing
+            if !distance_updated {
+                break;
             }
         }
+
+        if distance[&exit] == i32::MAX {
+            return Ok(PathfindingOutcome::unreachable(expanded));
+        }
+        let path = self.reconstruct_path(entrance, exit, maze, &mut predecessor, sender)?;
+        Ok(PathfindingOutcome::found(maze, path, expanded))
     }
 
     fn get_stats(&self) -> Option<super::PathfindingStats> {
@@ -166,3 +137,32 @@ impl PathfindingAlgorithm for BellmanFord {
         super::Algorithm::BellmanFord
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::{CellType, MazeType, Terrain};
+
+    #[test]
+    fn test_bellman_ford_avoids_expensive_terrain() {
+        let mut maze = Maze::new(6, 5, MazeType::Thick, Some(CellType::Path));
+        maze.mark_cell_as_entrance(Point { x: 1, y: 1 });
+        maze.mark_cell_as_exit(Point { x: 3, y: 1 });
+        maze.set_terrain(Point { x: 2, y: 1 }, Terrain::SwampHeavy);
+        maze.backup();
+
+        let (sender, _receiver) = channel();
+        BellmanFord.find_path(&mut maze, &sender).unwrap();
+
+        assert_ne!(
+            maze.get_cell(Point { x: 2, y: 1 }).get_type(),
+            CellType::FinalPath
+        );
+        assert_eq!(
+            maze.get_cell(Point { x: 2, y: 2 }).get_type(),
+            CellType::FinalPath
+        );
+    }
+}
@@ -1,9 +1,9 @@
-use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    sync::mpsc::Sender,
-};
+use std::collections::{HashMap, VecDeque};
 
-use super::{Algorithm, Movements, PathfindingAlgorithm, PathfindingResult, Point};
+use super::{
+    endpoints, send_final_path, send_step, Algorithm, FrameSink, PathfindingAlgorithm,
+    PathfindingError, PathfindingOutcome, Point, StepEvent,
+};
 
 #[derive(Default)]
 pub struct BFS;
@@ -14,77 +14,62 @@ impl BFS {
     }
 
     fn reconstruct_path(came_from: &HashMap<Point, Point>, mut current: Point) -> Vec<Point> {
-        let mut path = Vec::new();
-        let mut visited = HashSet::new();
-
-        while visited.insert(current) {
-            if let Some(&prev) = came_from.get(&current) {
-                path.push(current);
-                current = prev;
-            } else {
-                break; // Break the loop if current point has no predecessor
-            }
+        let mut path = vec![current];
+        while let Some(&prev) = came_from.get(&current) {
+            current = prev;
+            path.push(current);
         }
-
         path.reverse();
         path
     }
 }
 
 impl PathfindingAlgorithm for BFS {
-    fn find_path(&mut self, maze: &mut crate::maze::Maze, sender: &Sender<PathfindingResult>) {
+    fn find_path(
+        &mut self,
+        maze: &mut crate::maze::Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
         // Find entrance and exist coordinates
-        let entrance = maze.get_entrance().expect("Cannot find entrance point");
-        let exit = maze.get_exit().expect("Cannot find exit point");
+        let (entrance, exit) = endpoints(maze)?;
 
         let mut queue = VecDeque::new();
         let mut came_from: HashMap<Point, Point> = HashMap::new();
 
         queue.push_back(entrance);
 
+        let mut expanded = 0;
+        let mut frontier = vec![StepEvent::FrontierPush(entrance)];
         while let Some(current) = queue.pop_front() {
+            sender.expand()?;
+            expanded += 1;
             maze.mark_cell_as_visited(current);
-            sender
-                .send(PathfindingResult {
-                    maze: maze.clone(),
-                    stats: None,
-                })
-                .unwrap();
+            frontier.push(StepEvent::FrontierPop(current));
+            send_step(maze, sender, std::mem::take(&mut frontier))?;
 
             if current == exit {
                 // Reached the exit, reconstruct and visualize the path
                 let path = Self::reconstruct_path(&came_from, current);
-                for point in path.iter().skip(1) {
-                    maze.mark_cell_as_final_path(*point);
-
-                    // Send the updated maze to the main thread
-                    sender
-                        .send(PathfindingResult {
-                            maze: maze.clone(),
-                            stats: None,
-                        })
-                        .expect("Failed to send maze to the main thread");
-                }
-                break;
+                send_final_path(maze, sender, &path)?;
+                return Ok(PathfindingOutcome::found(maze, path, expanded));
             }
 
-            for (dx, dy) in &Movements::directions() {
-                let neighbor = Point {
-                    x: (current.x as i32 + dx) as usize,
-                    y: (current.y as i32 + dy) as usize,
-                };
-
-                if !maze.is_valid_coord(neighbor.x as i32, neighbor.y as i32)
-                    || maze.is_not_passable(current, neighbor)
+            for neighbor in maze.neighbors(current) {
+                // The entrance has no predecessor, it must not be queued a second time
+                if maze.is_not_passable(current, neighbor)
                     || came_from.contains_key(&neighbor)
+                    || neighbor == entrance
                 {
                     continue;
                 }
 
                 came_from.insert(neighbor, current);
                 queue.push_back(neighbor);
+                frontier.push(StepEvent::FrontierPush(neighbor));
             }
         }
+
+        Ok(PathfindingOutcome::unreachable(expanded))
     }
 
     fn name(&self) -> super::Algorithm {
@@ -0,0 +1,56 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, finish_maze, maze_rows, require_row_grid, send_frame, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Binary tree: every cell is joined to the cell above or to the left of it. Needs no
+/// memory at all, but the top row and left column are always straight corridors and all
+/// paths lean towards the top left corner.
+#[derive(Default)]
+pub struct BinaryTree;
+
+impl BinaryTree {
+    pub fn new() -> Self {
+        BinaryTree
+    }
+}
+
+impl MazeGenerationAlgorithm for BinaryTree {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        require_row_grid(&maze)?;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let rows = maze_rows(&maze, entrance);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                maze.mark_cell_as_path(cell);
+
+                let mut candidates = Vec::with_capacity(2);
+                if y > 0 {
+                    candidates.push(rows[y - 1][x]);
+                }
+                if x > 0 {
+                    candidates.push(row[x - 1]);
+                }
+                if let Some(&next) = candidates.choose(&mut rng) {
+                    carve_passage(&mut maze, cell, next);
+                }
+            }
+            send_frame(&mut maze, sender)?;
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
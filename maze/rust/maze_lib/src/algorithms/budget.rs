@@ -0,0 +1,277 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::Maze;
+
+use super::{FrameSink, PathfindingError, PathfindingResult};
+
+/// Why a run was cut off before it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Cancelled,
+    StepLimit,
+    TimeLimit,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::StepLimit => write!(f, "out of steps"),
+            StopReason::TimeLimit => write!(f, "out of time"),
+        }
+    }
+}
+
+/// Limits of a run, checked every time the algorithm sends a frame and every time a search
+/// expands a cell. Clones share the
+/// cancellation, so a run can be cancelled from another thread than the one it runs on.
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    cancelled: Arc<AtomicBool>,
+    stop_reason: Arc<Mutex<Option<StopReason>>>,
+    max_steps: Option<usize>,
+    time_limit: Option<Duration>,
+}
+
+impl Budget {
+    /// A budget without limits, the run stops only when cancelled.
+    pub fn new() -> Self {
+        Budget::default()
+    }
+
+    /// Stops the run before it sends more than `steps` frames.
+    pub fn with_max_steps(mut self, steps: usize) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// Stops the run once it has been running longer than `limit`.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Stops the run at its next frame.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Why the last run under this budget was cut off, `None` if it finished.
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        *self.stop_reason.lock().expect("Budget lock poisoned")
+    }
+
+    /// Wraps `sink` so the run is checked against this budget, see `BudgetSink`.
+    pub(crate) fn watch<'a>(
+        &'a self,
+        sink: Option<&'a dyn FrameSink>,
+        partial: Option<Maze>,
+    ) -> BudgetSink<'a> {
+        *self.stop_reason.lock().expect("Budget lock poisoned") = None;
+        BudgetSink {
+            budget: self,
+            sink,
+            started: Instant::now(),
+            steps: Cell::new(0),
+            expansions: Cell::new(0),
+            partial: RefCell::new(partial),
+        }
+    }
+}
+
+/// Forwards the frames of a run until its budget runs out, then answers with
+/// `PathfindingError::Stopped` so the algorithm returns.
+pub(crate) struct BudgetSink<'a> {
+    budget: &'a Budget,
+    sink: Option<&'a dyn FrameSink>,
+    started: Instant,
+    steps: Cell<usize>,
+    /// Cells expanded so far, for a search
+    expansions: Cell<usize>,
+    /// Maze the frames are applied to, what a cut off generator leaves behind
+    partial: RefCell<Option<Maze>>,
+}
+
+impl BudgetSink<'_> {
+    /// Frames let through so far.
+    pub(crate) fn steps(&self) -> usize {
+        self.steps.get()
+    }
+
+    /// Cells expanded so far, counted by `FrameSink::expand`.
+    pub(crate) fn expansions(&self) -> usize {
+        self.expansions.get()
+    }
+
+    pub(crate) fn into_partial(self) -> Option<Maze> {
+        self.partial.into_inner()
+    }
+
+    fn check(&self) -> Option<StopReason> {
+        if self.budget.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if self
+            .budget
+            .max_steps
+            .is_some_and(|steps| self.steps.get() >= steps)
+        {
+            Some(StopReason::StepLimit)
+        } else if self
+            .budget
+            .time_limit
+            .is_some_and(|limit| self.started.elapsed() > limit)
+        {
+            Some(StopReason::TimeLimit)
+        } else {
+            None
+        }
+    }
+
+    /// `PathfindingError::Stopped` once the budget has run out, remembering why.
+    fn enforce(&self) -> Result<(), PathfindingError> {
+        if let Some(reason) = self.check() {
+            *self
+                .budget
+                .stop_reason
+                .lock()
+                .expect("Budget lock poisoned") = Some(reason);
+            return Err(PathfindingError::Stopped(reason));
+        }
+        Ok(())
+    }
+}
+
+impl FrameSink for BudgetSink<'_> {
+    fn accept(&self, frame: PathfindingResult) -> Result<(), PathfindingError> {
+        self.enforce()?;
+
+        self.steps.set(self.steps.get() + 1);
+        if let Some(maze) = self.partial.borrow_mut().as_mut() {
+            frame.apply(maze);
+        }
+        match self.sink {
+            Some(sink) => sink.accept(frame),
+            None => Ok(()),
+        }
+    }
+
+    fn expand(&self) -> Result<(), PathfindingError> {
+        // The inner sink may hold the search back until the next step of a `FramePump`, a
+        // cancel in the meantime stops it before the cell is expanded
+        if let Some(sink) = self.sink {
+            sink.expand()?;
+        }
+        self.enforce()?;
+
+        self.expansions.set(self.expansions.get() + 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::{
+        algorithms::{Algorithm, FramePump, Point},
+        CellType, MazeType,
+    };
+
+    /// Drops the frames and cancels its budget once the search asks to expand more than
+    /// `at` cells.
+    struct CancelAt {
+        budget: Budget,
+        at: usize,
+        expansions: Cell<usize>,
+    }
+
+    impl FrameSink for CancelAt {
+        fn accept(&self, _frame: PathfindingResult) -> Result<(), PathfindingError> {
+            Ok(())
+        }
+
+        fn expand(&self) -> Result<(), PathfindingError> {
+            self.expansions.set(self.expansions.get() + 1);
+            if self.expansions.get() == self.at + 1 {
+                self.budget.cancel();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_budget_cuts_runs_off() {
+        let walls = Maze::new(15, 15, MazeType::Thick, Some(CellType::Wall));
+        let mut generator = Algorithm::Kruskal.get_maze_generator().unwrap();
+        let maze = generator
+            .carve_within(walls.clone(), Point::default(), 3, None, &Budget::new())
+            .unwrap();
+
+        // A cancelled budget stops the generator before it carves anything
+        let budget = Budget::new();
+        budget.cancel();
+        let partial = generator
+            .carve_within(walls.clone(), Point::default(), 3, None, &budget)
+            .unwrap();
+        assert_eq!(budget.stop_reason(), Some(StopReason::Cancelled));
+        assert_eq!(format!("{:?}", partial), format!("{:?}", walls));
+
+        let (sender, _receiver) = channel();
+        let budget = Budget::new().with_max_steps(3);
+        let outcome = Algorithm::BFS
+            .get_pathfinder()
+            .unwrap()
+            .find_path_within(&mut maze.clone(), &sender, &budget)
+            .unwrap();
+        let partial = outcome.stopped.unwrap();
+        assert_eq!(partial.reason, StopReason::StepLimit);
+        assert_eq!(partial.frames, 3);
+        // The search stops before the expansion that would send one frame too many
+        assert_eq!(outcome.expanded, 3);
+        assert_eq!(partial.visited.len(), 3);
+        assert!(!outcome.reachable);
+
+        // Expansions are checked whether they send a frame or not, like the passes of
+        // Bellman-Ford that improve nothing
+        for algorithm in Algorithm::pathfinding_algorithms() {
+            let budget = Budget::new();
+            let sink = CancelAt {
+                budget: budget.clone(),
+                at: 10,
+                expansions: Cell::new(0),
+            };
+            let outcome = algorithm
+                .get_pathfinder()
+                .unwrap()
+                .find_path_within(&mut maze.clone(), &sink, &budget)
+                .unwrap();
+            assert_eq!(outcome.stopped.unwrap().reason, StopReason::Cancelled);
+            assert_eq!(outcome.expanded, 10, "{}", algorithm);
+        }
+
+        let budget = Budget::new();
+        let mut pump = FramePump::find_path_within(
+            Algorithm::AStar.get_pathfinder().unwrap(),
+            maze,
+            budget.clone(),
+        );
+        assert!(pump.step().is_some());
+        budget.cancel();
+        let outcome = pump.finish().unwrap();
+        assert_eq!(outcome.stopped.unwrap().reason, StopReason::Cancelled);
+        assert_eq!(budget.stop_reason(), Some(StopReason::Cancelled));
+    }
+}
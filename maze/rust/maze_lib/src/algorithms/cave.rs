@@ -0,0 +1,274 @@
+use std::collections::VecDeque;
+
+use enum_iterator::Sequence;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{maze::Maze, CellType, MazeType};
+
+use super::{send_frame, FrameSink, MazeGenerationAlgorithm, Movements, PathfindingError, Point};
+
+/// How many cells start as walls and how often the field is smoothed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Sequence)]
+pub enum CaveStyle {
+    /// Winding caves of medium width
+    #[default]
+    Winding,
+    /// Fewer walls and more passes, wide caverns with smooth walls
+    Open,
+    /// A single pass over a denser field, ragged walls and narrow tunnels
+    Rough,
+}
+
+impl CaveStyle {
+    /// Share of cells that start as walls.
+    pub fn fill_ratio(&self) -> f64 {
+        match self {
+            CaveStyle::Winding => 0.45,
+            CaveStyle::Open => 0.4,
+            CaveStyle::Rough => 0.47,
+        }
+    }
+
+    /// Number of smoothing passes.
+    pub fn iterations(&self) -> usize {
+        match self {
+            CaveStyle::Winding => 4,
+            CaveStyle::Open => 6,
+            CaveStyle::Rough => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for CaveStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CaveStyle::Winding => "winding",
+                CaveStyle::Open => "open",
+                CaveStyle::Rough => "rough",
+            }
+        )
+    }
+}
+
+/// Cave map grown by a cellular automaton. The field is filled with random walls and
+/// smoothed, a cell turns into a wall when at least five of its eight neighbours are walls
+/// and stays one with four. Only the largest open region is kept, so the result has loops
+/// and open areas unlike a perfect maze. Supports thick walls mazes only, nothing is
+/// generated if the automaton leaves fewer than two open cells.
+pub struct Cave {
+    fill_ratio: f64,
+    iterations: usize,
+}
+
+impl Default for Cave {
+    fn default() -> Self {
+        Cave::with_style(CaveStyle::default())
+    }
+}
+
+impl Cave {
+    pub fn with_style(style: CaveStyle) -> Self {
+        Cave::new(style.fill_ratio(), style.iterations())
+    }
+
+    /// `fill_ratio` is the share of cells that start as walls, `iterations` the number of
+    /// smoothing passes.
+    pub fn new(fill_ratio: f64, iterations: usize) -> Self {
+        Cave {
+            fill_ratio: fill_ratio.clamp(0.0, 1.0),
+            iterations,
+        }
+    }
+
+    fn is_inside(maze: &Maze, x: i32, y: i32) -> bool {
+        x > 0 && y > 0 && maze.is_valid_coord(x, y)
+    }
+
+    fn is_wall(maze: &Maze, x: i32, y: i32) -> bool {
+        !Cave::is_inside(maze, x, y)
+            || maze
+                .get_cell(Point {
+                    x: x as usize,
+                    y: y as usize,
+                })
+                .get_type()
+                == CellType::Wall
+    }
+
+    fn smooth(maze: &Maze) -> Maze {
+        let mut next = maze.clone();
+        for y in 1..maze.height as i32 - 1 {
+            for x in 1..maze.width as i32 - 1 {
+                if !Cave::is_inside(maze, x, y) {
+                    continue;
+                }
+
+                let walls = (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .filter(|&(dx, dy)| (dx, dy) != (0, 0))
+                    .filter(|&(dx, dy)| Cave::is_wall(maze, x + dx, y + dy))
+                    .count();
+                let point = Point {
+                    x: x as usize,
+                    y: y as usize,
+                };
+                if walls >= 5 || (walls == 4 && Cave::is_wall(maze, x, y)) {
+                    next.mark_cell_as_wall(point);
+                } else {
+                    next.mark_cell_as_path(point);
+                }
+            }
+        }
+        next
+    }
+
+    /// Open cells reachable from `start` together with their distance from it.
+    fn flood_fill(maze: &Maze, start: Point) -> Vec<(Point, usize)> {
+        let mut reached = vec![false; maze.width * maze.height];
+        reached[maze.get_index(start.x, start.y)] = true;
+        let mut queue = VecDeque::from([(start, 0)]);
+        let mut region = Vec::new();
+
+        while let Some((current, distance)) = queue.pop_front() {
+            region.push((current, distance));
+            for (dx, dy) in Movements::directions() {
+                let x = current.x as i32 + dx;
+                let y = current.y as i32 + dy;
+                if Cave::is_wall(maze, x, y) {
+                    continue;
+                }
+
+                let next = Point {
+                    x: x as usize,
+                    y: y as usize,
+                };
+                let index = maze.get_index(next.x, next.y);
+                if !reached[index] {
+                    reached[index] = true;
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        region
+    }
+
+    /// Largest open region, every other open cell is filled with walls.
+    fn keep_largest_region(maze: &mut Maze) -> Vec<Point> {
+        let mut seen = vec![false; maze.width * maze.height];
+        let mut largest = Vec::new();
+        for y in 0..maze.height {
+            for x in 0..maze.width {
+                if seen[maze.get_index(x, y)] || Cave::is_wall(maze, x as i32, y as i32) {
+                    continue;
+                }
+
+                let region: Vec<Point> = Cave::flood_fill(maze, Point { x, y })
+                    .into_iter()
+                    .map(|(point, _)| point)
+                    .collect();
+                for point in &region {
+                    seen[maze.get_index(point.x, point.y)] = true;
+                }
+                if region.len() > largest.len() {
+                    largest = region;
+                }
+            }
+        }
+
+        let mut kept = vec![false; maze.width * maze.height];
+        for point in &largest {
+            kept[maze.get_index(point.x, point.y)] = true;
+        }
+        for y in 0..maze.height {
+            for x in 0..maze.width {
+                if !kept[maze.get_index(x, y)] {
+                    maze.mark_cell_as_wall(Point { x, y });
+                }
+            }
+        }
+        largest
+    }
+}
+
+impl MazeGenerationAlgorithm for Cave {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        // The cave fills the whole rectangle, shaped mazes are left alone
+        if maze.maze_type != MazeType::Thick || maze.is_masked() {
+            return Err(PathfindingError::InvalidMaze);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for y in 0..maze.height {
+            for x in 0..maze.width {
+                if Cave::is_inside(&maze, x as i32, y as i32) && !rng.gen_bool(self.fill_ratio) {
+                    maze.mark_cell_as_path(Point { x, y });
+                }
+            }
+        }
+        send_frame(&mut maze, sender)?;
+
+        for _ in 0..self.iterations {
+            maze = Cave::smooth(&maze);
+            send_frame(&mut maze, sender)?;
+        }
+
+        let region = Cave::keep_largest_region(&mut maze);
+        if region.len() < 2 {
+            return Err(PathfindingError::InvalidMaze);
+        }
+        send_frame(&mut maze, sender)?;
+
+        // The requested entrance may have been filled in, take the closest open cell instead
+        let entrance = *region
+            .iter()
+            .min_by_key(|point| point.x.abs_diff(entrance.x) + point.y.abs_diff(entrance.y))
+            .ok_or(PathfindingError::InvalidMaze)?;
+        let (exit, _) = *Cave::flood_fill(&maze, entrance)
+            .iter()
+            .max_by_key(|(_, distance)| *distance)
+            .ok_or(PathfindingError::InvalidMaze)?;
+
+        maze.mark_cell_as_entrance(entrance);
+        maze.mark_cell_as_exit(exit);
+        send_frame(&mut maze, sender)?;
+        maze.backup();
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cave_is_connected() {
+        for (style, seed) in
+            enum_iterator::all::<CaveStyle>().flat_map(|s| (0..5).map(move |i| (s, i)))
+        {
+            let maze = Cave::with_style(style)
+                .generate(MazeType::Thick, 41, 31, Point { x: 1, y: 1 }, seed, None)
+                .unwrap();
+            let entrance = maze.get_entrance().unwrap();
+            let exit = maze.get_exit().unwrap();
+
+            let region = Cave::flood_fill(&maze, entrance);
+            let open = (0..maze.height)
+                .flat_map(|y| (0..maze.width).map(move |x| Point { x, y }))
+                .filter(|&point| maze.get_cell(point).get_type() != CellType::Wall)
+                .count();
+            assert_eq!(region.len(), open);
+            assert!(region.iter().any(|(point, _)| *point == exit));
+            assert_ne!(entrance, exit);
+        }
+    }
+}
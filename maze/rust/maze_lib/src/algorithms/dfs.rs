@@ -1,22 +1,26 @@
-use rand::{rngs::ThreadRng, seq::SliceRandom};
-use std::{collections::HashSet, sync::mpsc::Sender};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::collections::HashSet;
 
-use crate::{maze::Maze, CellType, MazeType};
+use crate::maze::Maze;
 
 use super::{
-    Algorithm, MazeGenerationAlgorithm, Movements, PathfindingAlgorithm, PathfindingResult,
-    PathfindingStats, Point,
+    carve_passage, cell_neighbors, endpoints, finish_maze, send_frame, send_step, Algorithm,
+    FrameSink, MazeGenerationAlgorithm, PathfindingAlgorithm, PathfindingError, PathfindingOutcome,
+    PathfindingStats, Point, StepEvent,
 };
 
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Clone)]
 pub struct DFS {
     stats: PathfindingStats,
+    /// Pushes and pops of the stack that are not sent yet
+    frontier: Vec<StepEvent>,
 }
 
 impl DFS {
     pub fn new() -> Self {
         DFS {
             stats: PathfindingStats::default(),
+            frontier: Vec::new(),
         }
     }
 
@@ -25,118 +29,89 @@ impl DFS {
         current: Point,
         goal: Point,
         maze: &mut Maze,
-        sender: &Sender<PathfindingResult>,
+        sender: &dyn FrameSink,
         visited: &mut HashSet<Point>,
-    ) -> bool {
+        path: &mut Vec<Point>,
+    ) -> Result<bool, PathfindingError> {
+        sender.expand()?;
         visited.insert(current);
+        path.push(current);
+        self.frontier.push(StepEvent::FrontierPush(current));
         maze.mark_cell_as_final_path(current);
         self.stats.new_step();
 
         if current == goal {
-            return true;
+            return Ok(true);
         }
 
-        for (dx, dy) in &Movements::directions() {
-            let neighbor = Point {
-                x: (current.x as i32 + dx) as usize,
-                y: (current.y as i32 + dy) as usize,
-            };
-
-            if maze.is_valid_coord(neighbor.x as i32, neighbor.y as i32)
-                && !visited.contains(&Point {
-                    x: neighbor.x,
-                    y: neighbor.y,
-                })
-                && maze.is_passable(current, neighbor)
-            {
-                sender
-                    .send(PathfindingResult {
-                        maze: maze.clone(),
-                        stats: None,
-                    })
-                    .unwrap();
+        for neighbor in maze.neighbors(current) {
+            if !visited.contains(&neighbor) && maze.is_passable(current, neighbor) {
+                send_step(maze, sender, std::mem::take(&mut self.frontier))?;
 
                 // Mark the final path
                 maze.mark_cell_as_final_path(neighbor);
 
-                if self.depth_first_search(
-                    Point {
-                        x: neighbor.x,
-                        y: neighbor.y,
-                    },
-                    goal,
-                    maze,
-                    sender,
-                    visited,
-                ) {
-                    return true;
+                // A cell given up on stays visited, or the loops of a braided maze would be
+                // searched over and over
+                if self.depth_first_search(neighbor, goal, maze, sender, visited, path)? {
+                    return Ok(true);
                 } else {
-                    visited.remove(&neighbor);
                     maze.mark_cell_as_visited(neighbor);
                 }
             }
         }
 
-        false
+        path.pop();
+        self.frontier.push(StepEvent::FrontierPop(current));
+        Ok(false)
     }
 
     fn depth_first_maze_generation(
         current: Point,
         maze: &mut Maze,
-        rng: &mut ThreadRng,
-        sender: Option<&Sender<PathfindingResult>>,
-    ) -> bool {
-        let mut shuffled_directions = match maze.maze_type {
-            MazeType::Thick => Movements::directions_doubled(),
-            MazeType::Slim => Movements::directions(),
-        }
-        .to_vec();
-
-        shuffled_directions.shuffle(rng);
-        for &(dx, dy) in &shuffled_directions {
-            let new_x = current.x as i32 + dx;
-            let new_y = current.y as i32 + dy;
-
-            if maze.is_valid_coord(new_x, new_y) {
-                maze.mark_cell_as_path(current);
-                let neighbor = Point {
-                    x: new_x as usize,
-                    y: new_y as usize,
-                };
-
-                if maze.is_not_passable(current, neighbor) {
-                    maze.mark_cell_as_path(neighbor);
-                    match maze.maze_type {
-                        MazeType::Thick => {
-                            maze.mark_cell_as_path(Point {
-                                x: (current.x + neighbor.x) / 2,
-                                y: (current.y + neighbor.y) / 2,
-                            });
-                        }
-                        MazeType::Slim => maze.remove_walls_between_cells(current, neighbor),
-                    }
-                    if let Some(s) = sender {
-                        s.send(PathfindingResult {
-                            stats: None,
-                            maze: maze.clone(),
-                        })
-                        .unwrap();
-                    }
-                    DFS::depth_first_maze_generation(neighbor, maze, rng, sender);
-                }
+        rng: &mut impl Rng,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<(), PathfindingError> {
+        let mut neighbors = cell_neighbors(maze, current);
+        neighbors.shuffle(rng);
+
+        for neighbor in neighbors {
+            maze.mark_cell_as_path(current);
+            if maze.is_not_passable(current, neighbor) {
+                carve_passage(maze, current, neighbor);
+                send_frame(maze, sender)?;
+                DFS::depth_first_maze_generation(neighbor, maze, rng, sender)?;
             }
         }
-        false
+        Ok(())
     }
 }
 
 impl PathfindingAlgorithm for DFS {
-    fn find_path(&mut self, maze: &mut Maze, sender: &Sender<PathfindingResult>) {
-        let entrance = maze.get_entrance().expect("Cannot find entrance");
-        let exit = maze.get_exit().expect("Cannot find exit");
+    fn find_path(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
+        let (entrance, exit) = endpoints(maze)?;
 
+        self.stats = PathfindingStats::default();
+        self.frontier.clear();
         let mut visited = HashSet::new();
-        self.depth_first_search(entrance, exit, maze, sender, &mut visited);
+        let mut path = Vec::new();
+        let found =
+            self.depth_first_search(entrance, exit, maze, sender, &mut visited, &mut path)?;
+
+        let mut events = std::mem::take(&mut self.frontier);
+        if found {
+            events.push(StepEvent::PathFound(path.clone()));
+        }
+        send_step(maze, sender, events)?;
+        if found {
+            Ok(PathfindingOutcome::found(maze, path, self.stats.steps))
+        } else {
+            Ok(PathfindingOutcome::unreachable(self.stats.steps))
+        }
     }
 
     fn name(&self) -> super::Algorithm {
@@ -149,43 +124,19 @@ impl PathfindingAlgorithm for DFS {
 }
 
 impl MazeGenerationAlgorithm for DFS {
-    fn generate(
+    fn carve(
         &mut self,
-        maze_type: MazeType,
-        width: usize,
-        height: usize,
+        mut maze: Maze,
         entrance: Point,
-        sender: Option<&Sender<PathfindingResult>>,
-    ) -> Option<Maze> {
-        let mut maze = Maze::new(
-            width,
-            height,
-            maze_type,
-            Some(CellType::Wall),
-            //Some(match maze_type {
-            //    MazeType::Thick => CellType::Wall,
-            //    MazeType::Slim => CellType::Path,
-            //}),
-        );
-        let mut rng = rand::thread_rng();
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
 
         maze.mark_cell_as_path(entrance);
-        DFS::depth_first_maze_generation(entrance, &mut maze, &mut rng, sender);
-
-        maze.mark_cell_as_entrance(entrance);
-        let exit_point = maze.get_random_boundary_point(&mut rng);
-        maze.mark_cell_as_exit(exit_point);
-
-        maze.backup();
-
-        if let Some(s) = sender {
-            s.send(PathfindingResult {
-                stats: None,
-                maze: maze.clone(),
-            })
-            .unwrap();
-        }
+        DFS::depth_first_maze_generation(entrance, &mut maze, &mut rng, sender)?;
 
-        Some(maze)
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
     }
 }
@@ -1,11 +1,11 @@
-use std::{
-    collections::{BinaryHeap, HashMap},
-    sync::mpsc::Sender,
-};
+use std::collections::{BinaryHeap, HashMap};
 
 use crate::maze::Maze;
 
-use super::{Algorithm, Movements, PathfindingAlgorithm, PathfindingResult, Point};
+use super::{
+    endpoints, send_final_path, send_step, Algorithm, FrameSink, PathfindingAlgorithm,
+    PathfindingError, PathfindingOutcome, Point, StepEvent,
+};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 struct Node {
@@ -47,10 +47,13 @@ impl Dijkstra {
 }
 
 impl PathfindingAlgorithm for Dijkstra {
-    fn find_path(&mut self, maze: &mut Maze, sender: &Sender<PathfindingResult>) {
+    fn find_path(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
         // Find entrance and exit coordinates
-        let entrance = maze.get_entrance().expect("Cannot find entrance point");
-        let exit = maze.get_exit().expect("Cannot find exit point");
+        let (entrance, exit) = endpoints(maze)?;
 
         let mut open_set = BinaryHeap::new();
         let mut came_from: HashMap<Point, Point> = HashMap::new();
@@ -62,46 +65,33 @@ impl PathfindingAlgorithm for Dijkstra {
         });
         costs.insert(entrance, 0);
 
+        let mut expanded = 0;
+        let mut frontier = vec![StepEvent::FrontierPush(entrance)];
         while let Some(current_node) = open_set.pop() {
             let current = current_node.point;
+            // Skip stale queue entries, a cheaper route to this cell was already expanded
+            if current_node.cost > costs[&current] {
+                continue;
+            }
+            sender.expand()?;
+            expanded += 1;
             maze.mark_cell_as_visited(current);
-            sender
-                .send(PathfindingResult {
-                    stats: None,
-                    maze: maze.clone(),
-                })
-                .unwrap();
+            frontier.push(StepEvent::FrontierPop(current));
+            send_step(maze, sender, std::mem::take(&mut frontier))?;
 
             if current == exit {
                 // Reached the exit, reconstruct and visualize the path
                 let path = Self::reconstruct_path(&came_from, current);
-                for point in path.iter().skip(1) {
-                    maze.mark_cell_as_final_path(*point);
-
-                    // Send the updated maze to the mazin thread
-                    sender
-                        .send(PathfindingResult {
-                            stats: None,
-                            maze: maze.clone(),
-                        })
-                        .expect("Failed to send maze to the main thread");
-                }
-                break;
+                send_final_path(maze, sender, &path)?;
+                return Ok(PathfindingOutcome::found(maze, path, expanded));
             }
 
-            for (dx, dy) in &Movements::directions() {
-                let neighbor = Point {
-                    x: (current.x as i32 + dx) as usize,
-                    y: (current.y as i32 + dy) as usize,
-                };
-
-                if !maze.is_valid_coord(neighbor.x as i32, neighbor.y as i32)
-                    || maze.is_not_passable(current, neighbor)
-                {
+            for neighbor in maze.neighbors(current) {
+                if maze.is_not_passable(current, neighbor) {
                     continue;
                 }
 
-                let tentative_cost = costs[&current] + 1;
+                let tentative_cost = costs[&current] + maze.get_cost(neighbor);
 
                 if !costs.contains_key(&neighbor) || tentative_cost < costs[&neighbor] {
                     costs.insert(neighbor, tentative_cost);
@@ -110,10 +100,13 @@ impl PathfindingAlgorithm for Dijkstra {
                     open_set.push(Node {
                         point: neighbor,
                         cost: tentative_cost,
-                    })
+                    });
+                    frontier.push(StepEvent::FrontierPush(neighbor));
                 }
             }
         }
+
+        Ok(PathfindingOutcome::unreachable(expanded))
     }
 
     fn name(&self) -> super::Algorithm {
@@ -124,3 +117,55 @@ impl PathfindingAlgorithm for Dijkstra {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::{CellType, MazeType, Terrain};
+
+    #[test]
+    fn test_dijkstra_avoids_expensive_terrain() {
+        let mut maze = Maze::new(6, 5, MazeType::Thick, Some(CellType::Path));
+        maze.mark_cell_as_entrance(Point { x: 1, y: 1 });
+        maze.mark_cell_as_exit(Point { x: 3, y: 1 });
+        maze.set_terrain(Point { x: 2, y: 1 }, Terrain::SwampHeavy);
+        maze.backup();
+
+        let (sender, _receiver) = channel();
+        Dijkstra::new().find_path(&mut maze, &sender).unwrap();
+
+        assert_ne!(
+            maze.get_cell(Point { x: 2, y: 1 }).get_type(),
+            CellType::FinalPath
+        );
+        assert_eq!(
+            maze.get_cell(Point { x: 2, y: 2 }).get_type(),
+            CellType::FinalPath
+        );
+    }
+
+    #[test]
+    fn test_dijkstra_takes_stairs() {
+        // One row of two cells on both levels, the exit is only reachable upstairs
+        let mut maze = Maze::new(3, 2, MazeType::Layered(2), Some(CellType::Path));
+        maze.remove_walls_between_cells(Point { x: 0, y: 0 }, Point { x: 0, y: 2 });
+        maze.remove_walls_between_cells(Point { x: 0, y: 2 }, Point { x: 1, y: 2 });
+        maze.mark_cell_as_entrance(Point { x: 0, y: 0 });
+        maze.mark_cell_as_exit(Point { x: 1, y: 2 });
+        maze.backup();
+
+        let (sender, _receiver) = channel();
+        Dijkstra::new().find_path(&mut maze, &sender).unwrap();
+
+        assert_eq!(
+            maze.get_cell(Point { x: 0, y: 2 }).get_type(),
+            CellType::FinalPath
+        );
+        assert_ne!(
+            maze.get_cell(Point { x: 1, y: 0 }).get_type(),
+            CellType::FinalPath
+        );
+    }
+}
@@ -27,3 +27,96 @@ impl Movements {
         }
     }
 }
+
+/// Sides of a cell in a hexagonal maze. Rows are offset, every odd row is shifted half a
+/// cell to the right, so the diagonal neighbours depend on the row.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HexDirection {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::East,
+        HexDirection::SouthEast,
+        HexDirection::SouthWest,
+        HexDirection::West,
+        HexDirection::NorthWest,
+        HexDirection::NorthEast,
+    ];
+
+    /// Wall bit of this side in `MazeCell::walls`.
+    pub fn wall(&self) -> u8 {
+        1 << (*self as u8)
+    }
+
+    pub fn opposite(&self) -> HexDirection {
+        HexDirection::ALL[(*self as usize + 3) % 6]
+    }
+
+    /// Offset of the neighbour on this side of a cell in row `y`.
+    pub fn offset(&self, y: usize) -> (i32, i32) {
+        let shift = (y % 2) as i32;
+        match self {
+            HexDirection::East => (1, 0),
+            HexDirection::West => (-1, 0),
+            HexDirection::SouthEast => (shift, 1),
+            HexDirection::SouthWest => (shift - 1, 1),
+            HexDirection::NorthEast => (shift, -1),
+            HexDirection::NorthWest => (shift - 1, -1),
+        }
+    }
+
+    /// Side of `current` that `neighbor` lies on.
+    pub fn between(current: Point, neighbor: Point) -> HexDirection {
+        let offset = Movements::calculate_direction(current, neighbor);
+        *HexDirection::ALL
+            .iter()
+            .find(|direction| direction.offset(current.y) == offset)
+            .expect("Cells are not neighbours")
+    }
+}
+
+/// Sides of a cell in a polar maze. Rings further out are split into more cells, so a
+/// cell can have several outward neighbours. The wall towards each of them is kept by the
+/// outer cell as its inward wall, `Outward` has no wall bit of its own.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PolarDirection {
+    Inward,
+    Outward,
+    Clockwise,
+    CounterClockwise,
+}
+
+impl PolarDirection {
+    /// Sides with a wall bit in `MazeCell::walls`.
+    pub const WALLS: [PolarDirection; 3] = [
+        PolarDirection::Inward,
+        PolarDirection::Clockwise,
+        PolarDirection::CounterClockwise,
+    ];
+
+    /// Wall bit of this side in `MazeCell::walls`, zero for `Outward`.
+    pub fn wall(&self) -> u8 {
+        match self {
+            PolarDirection::Inward => 0b001,
+            PolarDirection::Clockwise => 0b010,
+            PolarDirection::CounterClockwise => 0b100,
+            PolarDirection::Outward => 0,
+        }
+    }
+
+    pub fn opposite(&self) -> PolarDirection {
+        match self {
+            PolarDirection::Inward => PolarDirection::Outward,
+            PolarDirection::Outward => PolarDirection::Inward,
+            PolarDirection::Clockwise => PolarDirection::CounterClockwise,
+            PolarDirection::CounterClockwise => PolarDirection::Clockwise,
+        }
+    }
+}
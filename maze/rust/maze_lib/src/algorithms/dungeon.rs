@@ -0,0 +1,263 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{maze::Maze, MazeType};
+
+use super::{send_frame, FrameSink, MazeGenerationAlgorithm, PathfindingError, Point};
+
+#[derive(Debug, Clone, Copy)]
+struct Room {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Room {
+    fn center(&self) -> Point {
+        Point {
+            x: self.x + self.width / 2,
+            y: self.y + self.height / 2,
+        }
+    }
+
+    /// Rooms also must not touch, so there is always a wall between them.
+    fn overlaps(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+
+    fn distance(&self, other: &Room) -> usize {
+        let (a, b) = (self.center(), other.center());
+        a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+    }
+}
+
+/// Roguelike dungeon: non-overlapping rectangular rooms joined by corridors along a
+/// minimum spanning tree over the room centres, plus a few extra corridors that create
+/// loops. The entrance goes into the room closest to the requested entrance and the exit
+/// into the room furthest from it. Supports thick walls mazes only, nothing is generated
+/// if fewer than two rooms fit.
+pub struct Dungeon {
+    room_attempts: usize,
+    min_room_size: usize,
+    max_room_size: usize,
+    loop_chance: f64,
+}
+
+impl Default for Dungeon {
+    fn default() -> Self {
+        Dungeon::new(40, 3, 9, 0.25)
+    }
+}
+
+impl Dungeon {
+    /// Tries to place `room_attempts` rooms with sides between `min_room_size` and
+    /// `max_room_size`, every room gets an extra corridor to its nearest room with a
+    /// chance of `loop_chance`.
+    pub fn new(
+        room_attempts: usize,
+        min_room_size: usize,
+        max_room_size: usize,
+        loop_chance: f64,
+    ) -> Self {
+        let min_room_size = min_room_size.max(1);
+        Dungeon {
+            room_attempts,
+            min_room_size,
+            max_room_size: max_room_size.max(min_room_size),
+            loop_chance: loop_chance.clamp(0.0, 1.0),
+        }
+    }
+
+    fn place_rooms(&self, maze: &Maze, rng: &mut impl Rng) -> Vec<Room> {
+        let mut rooms: Vec<Room> = Vec::new();
+        // Rooms stay inside the outer wall, `is_valid_coord` excludes the last row and column
+        let (inner_width, inner_height) =
+            (maze.width.saturating_sub(2), maze.height.saturating_sub(2));
+
+        for _ in 0..self.room_attempts {
+            let width = rng.gen_range(self.min_room_size..=self.max_room_size);
+            let height = rng.gen_range(self.min_room_size..=self.max_room_size);
+            if width >= inner_width || height >= inner_height {
+                continue;
+            }
+
+            let room = Room {
+                x: rng.gen_range(1..inner_width - width + 1),
+                y: rng.gen_range(1..inner_height - height + 1),
+                width,
+                height,
+            };
+            if !rooms.iter().any(|other| room.overlaps(other)) {
+                rooms.push(room);
+            }
+        }
+        rooms
+    }
+
+    /// Edges of the minimum spanning tree over the room centres (Prim's algorithm).
+    fn spanning_tree(rooms: &[Room]) -> Vec<(usize, usize)> {
+        let mut in_tree = vec![false; rooms.len()];
+        // Closest tree room and its distance for every room outside the tree
+        let mut closest: Vec<(usize, usize)> = rooms
+            .iter()
+            .map(|room| (0, room.distance(&rooms[0])))
+            .collect();
+        in_tree[0] = true;
+
+        let mut edges = Vec::new();
+        while edges.len() + 1 < rooms.len() {
+            let (next, &(from, _)) = closest
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !in_tree[*index])
+                .min_by_key(|(_, (_, distance))| *distance)
+                .unwrap();
+            in_tree[next] = true;
+            edges.push((from, next));
+
+            for (index, room) in rooms.iter().enumerate() {
+                let distance = room.distance(&rooms[next]);
+                if !in_tree[index] && distance < closest[index].1 {
+                    closest[index] = (next, distance);
+                }
+            }
+        }
+        edges
+    }
+
+    /// L-shaped corridor between the centres of both rooms.
+    fn carve_corridor(maze: &mut Maze, from: Point, to: Point, rng: &mut impl Rng) {
+        let corner = if rng.gen_bool(0.5) {
+            Point { x: to.x, y: from.y }
+        } else {
+            Point { x: from.x, y: to.y }
+        };
+
+        for (start, end) in [(from, corner), (corner, to)] {
+            for x in start.x.min(end.x)..=start.x.max(end.x) {
+                for y in start.y.min(end.y)..=start.y.max(end.y) {
+                    maze.mark_cell_as_path(Point { x, y });
+                }
+            }
+        }
+    }
+}
+
+impl MazeGenerationAlgorithm for Dungeon {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        // Rooms are placed anywhere in the rectangle, shaped mazes are left alone
+        if maze.maze_type != MazeType::Thick || maze.is_masked() {
+            return Err(PathfindingError::InvalidMaze);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let rooms = self.place_rooms(&maze, &mut rng);
+        if rooms.len() < 2 {
+            return Err(PathfindingError::InvalidMaze);
+        }
+        for room in &rooms {
+            for y in room.y..room.y + room.height {
+                for x in room.x..room.x + room.width {
+                    maze.mark_cell_as_path(Point { x, y });
+                }
+            }
+            send_frame(&mut maze, sender)?;
+        }
+
+        let mut corridors = Dungeon::spanning_tree(&rooms);
+        for (index, room) in rooms.iter().enumerate() {
+            if !rng.gen_bool(self.loop_chance) {
+                continue;
+            }
+
+            let nearest = (0..rooms.len())
+                .filter(|&other| {
+                    other != index
+                        && !corridors.contains(&(index, other))
+                        && !corridors.contains(&(other, index))
+                })
+                .min_by_key(|&other| room.distance(&rooms[other]));
+            if let Some(other) = nearest {
+                corridors.push((index, other));
+            }
+        }
+        for (from, to) in corridors {
+            Dungeon::carve_corridor(
+                &mut maze,
+                rooms[from].center(),
+                rooms[to].center(),
+                &mut rng,
+            );
+            send_frame(&mut maze, sender)?;
+        }
+
+        // Entrance in the room closest to the requested entrance, exit in the room furthest away
+        let distance = |room: &Room| {
+            let center = room.center();
+            center.x.abs_diff(entrance.x) + center.y.abs_diff(entrance.y)
+        };
+        let first = rooms
+            .iter()
+            .min_by_key(|room| distance(room))
+            .ok_or(PathfindingError::InvalidMaze)?;
+        let last = rooms
+            .iter()
+            .max_by_key(|room| room.distance(first))
+            .ok_or(PathfindingError::InvalidMaze)?;
+
+        maze.mark_cell_as_entrance(first.center());
+        maze.mark_cell_as_exit(last.center());
+        send_frame(&mut maze, sender)?;
+        maze.backup();
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{algorithms::Movements, CellType};
+
+    #[test]
+    fn test_dungeon_is_connected() {
+        for seed in 0..5 {
+            let maze = Dungeon::default()
+                .generate(MazeType::Thick, 41, 41, Point { x: 1, y: 1 }, seed, None)
+                .unwrap();
+            let entrance = maze.get_entrance().unwrap();
+            let exit = maze.get_exit().unwrap();
+            assert_ne!(entrance, exit);
+
+            let open: HashSet<Point> = (0..maze.height)
+                .flat_map(|y| (0..maze.width).map(move |x| Point { x, y }))
+                .filter(|&point| maze.get_cell(point).get_type() != CellType::Wall)
+                .collect();
+            let mut reached = HashSet::from([entrance]);
+            let mut stack = vec![entrance];
+            while let Some(current) = stack.pop() {
+                for (dx, dy) in Movements::directions() {
+                    let next = Point {
+                        x: (current.x as i32 + dx) as usize,
+                        y: (current.y as i32 + dy) as usize,
+                    };
+                    if open.contains(&next) && reached.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            assert_eq!(reached, open);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{maze::Maze, CellType, MazeCell, MazeType, SlimWallsCellType};
+
+use super::{
+    carve_passage, finish_maze, maze_cells, require_row_grid, send_frame, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Eller's algorithm. Builds the maze one row at a time and only remembers which cells of
+/// the current row are already connected, so the maze can be as tall as needed.
+#[derive(Default)]
+pub struct Eller;
+
+impl Eller {
+    pub fn new() -> Self {
+        Eller
+    }
+
+    /// Endless stream of maze rows, `width` cells each.
+    pub fn rows(width: usize, seed: u64) -> EllerRows {
+        EllerRows {
+            width,
+            sets: vec![None; width],
+            next_set: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+/// Rows of a slim maze produced by Eller's algorithm. Every row is open towards the row
+/// yielded before it, call `last_row` to close the maze off.
+#[derive(Debug)]
+pub struct EllerRows {
+    width: usize,
+    /// Set of every cell in the row being built, `None` if it is not connected from above
+    sets: Vec<Option<usize>>,
+    next_set: usize,
+    rng: StdRng,
+}
+
+impl EllerRows {
+    /// Bottom row of a finite maze, joins everything that is still disconnected.
+    pub fn last_row(&mut self) -> Vec<MazeCell> {
+        self.build_row(true)
+    }
+
+    fn merge(&mut self, from: usize, into: usize) {
+        for set in self.sets.iter_mut() {
+            if *set == Some(from) {
+                *set = Some(into);
+            }
+        }
+    }
+
+    fn build_row(&mut self, last: bool) -> Vec<MazeCell> {
+        let mut row: Vec<MazeCell> = self
+            .sets
+            .iter()
+            .map(|set| {
+                let mut cell = MazeCell::new(CellType::Path);
+                cell.set_top_wall(set.is_none());
+                cell
+            })
+            .collect();
+
+        for set in self.sets.iter_mut() {
+            if set.is_none() {
+                *set = Some(self.next_set);
+                self.next_set += 1;
+            }
+        }
+
+        // Randomly join neighbouring cells from different sets, the last row joins all of them
+        for x in 0..self.width.saturating_sub(1) {
+            let (left, right) = (self.sets[x].unwrap(), self.sets[x + 1].unwrap());
+            if left != right && (last || self.rng.gen_bool(0.5)) {
+                row[x].set_right_wall(false);
+                row[x + 1].set_left_wall(false);
+                self.merge(right, left);
+            }
+        }
+
+        if last {
+            return row;
+        }
+
+        // Every set continues down at least once, so no part of the maze gets cut off
+        let mut next_sets: Vec<Option<usize>> = self
+            .sets
+            .iter()
+            .map(|&set| if self.rng.gen_bool(0.5) { set } else { None })
+            .collect();
+        for x in 0..self.width {
+            let set = self.sets[x];
+            if !next_sets.contains(&set) {
+                let cells: Vec<usize> = (0..self.width).filter(|&i| self.sets[i] == set).collect();
+                let down = *cells.choose(&mut self.rng).unwrap();
+                next_sets[down] = set;
+            }
+        }
+
+        for (cell, next) in row.iter_mut().zip(&next_sets) {
+            cell.set_bottom_wall(next.is_none());
+        }
+        self.sets = next_sets;
+
+        row
+    }
+}
+
+impl Iterator for EllerRows {
+    type Item = Vec<MazeCell>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.build_row(false))
+    }
+}
+
+impl MazeGenerationAlgorithm for Eller {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        require_row_grid(&maze)?;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let cells = maze_cells(&maze, entrance);
+        let first = *cells.first().ok_or(PathfindingError::InvalidMaze)?;
+        let columns = cells.iter().filter(|cell| cell.y == first.y).count();
+        let row_count = cells.len() / columns;
+
+        let mut rows = Eller::rows(columns, rng.gen());
+        for (y, cell_row) in cells.chunks(columns).enumerate() {
+            let row = if y + 1 == row_count {
+                rows.last_row()
+            } else {
+                rows.next().ok_or(PathfindingError::InvalidMaze)?
+            };
+
+            for (&point, cell) in cell_row.iter().zip(row) {
+                match maze.maze_type {
+                    MazeType::Thick => {
+                        maze.mark_cell_as_path(point);
+                        if !cell.has_right_wall() {
+                            maze.mark_cell_as_path(Point {
+                                x: point.x + 1,
+                                y: point.y,
+                            });
+                        }
+                        if !cell.has_bottom_wall() {
+                            maze.mark_cell_as_path(Point {
+                                x: point.x,
+                                y: point.y + 1,
+                            });
+                        }
+                    }
+                    MazeType::Slim | MazeType::Weave => maze.set_cell(point.x, point.y, cell),
+                    MazeType::Polar | MazeType::Layered(_) | MazeType::Toroidal(_) => {
+                        return Err(PathfindingError::InvalidMaze)
+                    }
+                    // The cells to the right and below are neighbours in hexagonal rows too
+                    MazeType::Hex => {
+                        maze.mark_cell_as_path(point);
+                        if !cell.has_right_wall() {
+                            carve_passage(
+                                &mut maze,
+                                point,
+                                Point {
+                                    x: point.x + 1,
+                                    ..point
+                                },
+                            );
+                        }
+                        if !cell.has_bottom_wall() {
+                            carve_passage(
+                                &mut maze,
+                                point,
+                                Point {
+                                    y: point.y + 1,
+                                    ..point
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+            send_frame(&mut maze, sender)?;
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_are_consistent() {
+        let mut rows = Eller::rows(12, 5);
+        let mut previous = rows.next().unwrap();
+        assert!(previous.iter().all(|cell| cell.has_top_wall()));
+
+        for _ in 0..100 {
+            let row = rows.next().unwrap();
+            assert_eq!(row.len(), 12);
+            for (above, below) in previous.iter().zip(&row) {
+                assert_eq!(above.has_bottom_wall(), below.has_top_wall());
+            }
+            assert!(row
+                .windows(2)
+                .all(|pair| pair[0].has_right_wall() == pair[1].has_left_wall()));
+            previous = row;
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+use crate::{Maze, MazeType};
+
+use super::{
+    Budget, FrameSink, MazeGenerationAlgorithm, PathfindingAlgorithm, PathfindingError,
+    PathfindingOutcome, PathfindingResult, Point,
+};
+
+/// Pumps the frames of an algorithm running on a thread of its own, one step at a time.
+/// The algorithm waits before every step until `step` asks for it, so nothing runs ahead
+/// while the caller pauses, and several pumps can be advanced in turns from one thread.
+/// Dropping a pump stops its algorithm at its next step.
+///
+/// The algorithms are not resumable by themselves, so every pump holds a thread until its
+/// algorithm returns. A step of a search is one expanded cell with the frames it sent, see
+/// `FrameSink::expand`, and once the search returns, the frames after its last expansion,
+/// like the found path, follow one per step. A step of a generator is one frame, usually a
+/// carved passage. The random walks of Aldous-Broder and Wilson batch many walking steps
+/// into a frame, see `walk_frame_interval`.
+#[derive(Debug)]
+pub struct FramePump<T> {
+    /// Steps of the algorithm, `true` marks the last one
+    steps: Receiver<(PathfindingResult, bool)>,
+    /// Lets the algorithm run its next step
+    resume: SyncSender<()>,
+    handle: JoinHandle<Result<T, PathfindingError>>,
+    done: bool,
+}
+
+impl<T: Send + 'static> FramePump<T> {
+    /// Starts `run`, which sends its frames to the sink it is given, a step per frame.
+    pub fn new<F>(run: F) -> Self
+    where
+        F: FnOnce(&dyn FrameSink) -> Result<T, PathfindingError> + Send + 'static,
+    {
+        FramePump::start(run, false)
+    }
+
+    fn start<F>(run: F, by_expansion: bool) -> Self
+    where
+        F: FnOnce(&dyn FrameSink) -> Result<T, PathfindingError> + Send + 'static,
+    {
+        // Channels without capacity hand every step over directly
+        let (sender, steps) = sync_channel(0);
+        let (resume, resumed) = sync_channel(0);
+        let handle = thread::spawn(move || {
+            // Nothing runs before the first step is asked for
+            resumed.recv().map_err(|_| PathfindingError::Disconnected)?;
+            let sink = PumpSink {
+                steps: sender,
+                resumed,
+                by_expansion,
+                pending: RefCell::new(Vec::new()),
+                expanding: Cell::new(false),
+            };
+            let result = run(&sink);
+            sink.flush();
+            result
+        });
+        FramePump {
+            steps,
+            resume,
+            handle,
+            done: false,
+        }
+    }
+
+    /// Runs the algorithm for one step and returns its frame, `None` once it has finished.
+    pub fn step(&mut self) -> Option<PathfindingResult> {
+        if self.done {
+            return None;
+        }
+        let step = self
+            .resume
+            .send(())
+            .ok()
+            .and_then(|_| self.steps.recv().ok());
+        self.done = step.as_ref().is_none_or(|&(_, last)| last);
+        step.map(|(frame, _)| frame)
+    }
+
+    /// Whether the last step has been returned by `step`, the next call returns `None`.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Runs the algorithm to its end, dropping the frames not pulled yet, and returns its
+    /// result. An algorithm that panicked returns `PathfindingError::Panicked`.
+    pub fn finish(mut self) -> Result<T, PathfindingError> {
+        while self.step().is_some() {}
+        self.handle
+            .join()
+            .unwrap_or(Err(PathfindingError::Panicked))
+    }
+}
+
+impl FramePump<PathfindingOutcome> {
+    /// Searches `maze` an expansion per step, the frames apply to a copy of it as it is now.
+    pub fn find_path(mut pathfinder: Box<dyn PathfindingAlgorithm + Send>, mut maze: Maze) -> Self {
+        FramePump::start(move |sender| pathfinder.find_path(&mut maze, sender), true)
+    }
+
+    /// Searches `maze` until `budget` runs out, see `PathfindingAlgorithm::find_path_within`.
+    /// Cancelling a clone of `budget` stops the search before its next expansion.
+    pub fn find_path_within(
+        mut pathfinder: Box<dyn PathfindingAlgorithm + Send>,
+        mut maze: Maze,
+        budget: Budget,
+    ) -> Self {
+        FramePump::start(
+            move |sender| pathfinder.find_path_within(&mut maze, sender, &budget),
+            true,
+        )
+    }
+}
+
+impl FramePump<Maze> {
+    /// Generates a maze, see `MazeGenerationAlgorithm::generate`. The frames apply to a
+    /// maze of walls of the same size.
+    pub fn generate(
+        mut generator: Box<dyn MazeGenerationAlgorithm + Send>,
+        maze_type: MazeType,
+        width: usize,
+        height: usize,
+        entrance: Point,
+        seed: u64,
+    ) -> Self {
+        FramePump::new(move |sender| {
+            generator.generate(maze_type, width, height, entrance, seed, Some(sender))
+        })
+    }
+
+    /// Carves `maze` until `budget` runs out, see `MazeGenerationAlgorithm::carve_within`.
+    /// The frames apply to `maze` as it is now.
+    pub fn carve_within(
+        mut generator: Box<dyn MazeGenerationAlgorithm + Send>,
+        maze: Maze,
+        entrance: Point,
+        seed: u64,
+        budget: Budget,
+    ) -> Self {
+        FramePump::new(move |sender| {
+            generator.carve_within(maze, entrance, seed, Some(sender), &budget)
+        })
+    }
+}
+
+/// Collects the frames of the algorithm on its thread and hands them to the pump a step
+/// at a time.
+struct PumpSink {
+    steps: SyncSender<(PathfindingResult, bool)>,
+    resumed: Receiver<()>,
+    /// Steps end with an expansion instead of a frame
+    by_expansion: bool,
+    /// Frames of the step that is not handed over yet
+    pending: RefCell<Vec<PathfindingResult>>,
+    /// Whether the search has started its first expansion
+    expanding: Cell<bool>,
+}
+
+impl PumpSink {
+    /// Hands a step over and, unless it is the last one, waits until the next is asked for.
+    fn hand_over(&self, frame: PathfindingResult, last: bool) -> Result<(), PathfindingError> {
+        self.steps
+            .send((frame, last))
+            .map_err(|_| PathfindingError::Disconnected)?;
+        if !last {
+            self.resumed
+                .recv()
+                .map_err(|_| PathfindingError::Disconnected)?;
+        }
+        Ok(())
+    }
+
+    /// Hands over the frames left once the algorithm returned, a step each. The last step
+    /// is empty if there are none, so the pump always learns that it is done.
+    fn flush(&self) {
+        let mut frames = self.pending.take();
+        if frames.is_empty() {
+            frames.push(PathfindingResult::default());
+        }
+        let count = frames.len();
+        for (index, frame) in frames.into_iter().enumerate() {
+            if self.hand_over(frame, index + 1 == count).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+impl FrameSink for PumpSink {
+    fn accept(&self, frame: PathfindingResult) -> Result<(), PathfindingError> {
+        // A generator runs a frame ahead, so its last frame is known to be the last
+        if !self.by_expansion {
+            let previous = self.pending.borrow_mut().pop();
+            if let Some(previous) = previous {
+                self.hand_over(previous, false)?;
+            }
+        }
+        self.pending.borrow_mut().push(frame);
+        Ok(())
+    }
+
+    fn expand(&self) -> Result<(), PathfindingError> {
+        // Frames sent before the first expansion belong to its step
+        if self.by_expansion && self.expanding.replace(true) {
+            let events = self
+                .pending
+                .take()
+                .into_iter()
+                .flat_map(|frame| frame.events)
+                .collect();
+            self.hand_over(PathfindingResult { events }, false)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::{algorithms::Algorithm, CellType};
+
+    #[test]
+    fn test_pump_matches_a_full_run() {
+        let mut pump = FramePump::generate(
+            Algorithm::Kruskal.get_maze_generator().unwrap(),
+            MazeType::Thick,
+            15,
+            15,
+            Point::default(),
+            9,
+        );
+        let mut copy = Maze::new(15, 15, MazeType::Thick, Some(CellType::Wall));
+        while !pump.is_done() {
+            pump.step().unwrap().apply(&mut copy);
+        }
+        assert!(pump.step().is_none());
+        let maze = pump.finish().unwrap();
+        assert_eq!(format!("{:?}", copy), format!("{:?}", maze));
+
+        let mut pump = FramePump::find_path(Algorithm::BFS.get_pathfinder().unwrap(), maze.clone());
+        assert!(pump.step().is_some());
+        let outcome = pump.finish().unwrap();
+
+        let (sender, _receiver) = channel();
+        let mut searched = maze.clone();
+        let expected = Algorithm::BFS
+            .get_pathfinder()
+            .unwrap()
+            .find_path(&mut searched, &sender)
+            .unwrap();
+        assert_eq!(outcome, expected);
+    }
+
+    #[test]
+    fn test_steps_are_expansions() {
+        let maze = Algorithm::Kruskal
+            .get_maze_generator()
+            .unwrap()
+            .generate(MazeType::Thick, 21, 21, Point::default(), 4, None)
+            .unwrap();
+
+        for algorithm in Algorithm::pathfinding_algorithms() {
+            for steps in [1, 4, 9] {
+                let budget = Budget::new();
+                let mut pump = FramePump::find_path_within(
+                    algorithm.get_pathfinder().unwrap(),
+                    maze.clone(),
+                    budget.clone(),
+                );
+                for _ in 0..steps {
+                    assert!(pump.step().is_some());
+                    assert!(!pump.is_done());
+                }
+                // The search waits before its next expansion, so it stops right there
+                budget.cancel();
+                let outcome = pump.finish().unwrap();
+                assert!(outcome.stopped.is_some());
+                assert_eq!(outcome.expanded, steps, "{}", algorithm);
+            }
+        }
+    }
+
+    #[test]
+    fn test_panic_is_reported() {
+        let mut pump = FramePump::<Maze>::new(|_| panic!("Algorithm failed"));
+        assert!(pump.step().is_none());
+        assert!(pump.is_done());
+        assert_eq!(pump.finish().unwrap_err(), PathfindingError::Panicked);
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use enum_iterator::Sequence;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, send_frame, FrameSink, MazeGenerationAlgorithm,
+    PathfindingError, Point,
+};
+
+/// Which active cell the growing tree continues from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Sequence)]
+pub enum SelectionPolicy {
+    /// Always the newest cell, long winding corridors like the backtracker
+    #[default]
+    Newest,
+    /// Any cell, lots of short branches like Prim's algorithm
+    Random,
+    /// Always the oldest cell, long straight corridors spreading from the entrance
+    Oldest,
+    /// Newest cell three times as often as a random one, long corridors with side branches
+    MostlyNewest,
+    /// Newest and random cells equally often
+    Mixed,
+    /// Random cell three times as often as the newest one, short branches with a few
+    /// longer corridors
+    MostlyRandom,
+}
+
+impl SelectionPolicy {
+    /// Relative weights of picking the newest, a random and the oldest cell.
+    pub fn weights(&self) -> [u32; 3] {
+        match self {
+            SelectionPolicy::Newest => [1, 0, 0],
+            SelectionPolicy::Random => [0, 1, 0],
+            SelectionPolicy::Oldest => [0, 0, 1],
+            SelectionPolicy::MostlyNewest => [3, 1, 0],
+            SelectionPolicy::Mixed => [1, 1, 0],
+            SelectionPolicy::MostlyRandom => [1, 3, 0],
+        }
+    }
+}
+
+impl std::fmt::Display for SelectionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SelectionPolicy::Newest => "newest",
+                SelectionPolicy::Random => "random",
+                SelectionPolicy::Oldest => "oldest",
+                SelectionPolicy::MostlyNewest => "newest/random 3:1",
+                SelectionPolicy::Mixed => "newest/random 1:1",
+                SelectionPolicy::MostlyRandom => "newest/random 1:3",
+            }
+        )
+    }
+}
+
+/// Growing tree: keeps a list of active cells, carves from one of them into an unvisited
+/// neighbour and drops cells that have none left. The selection policy decides between
+/// DFS-like and Prim-like mazes.
+pub struct GrowingTree {
+    /// Weights of picking the newest, a random and the oldest active cell
+    weights: [u32; 3],
+}
+
+impl Default for GrowingTree {
+    fn default() -> Self {
+        GrowingTree::new(SelectionPolicy::default())
+    }
+}
+
+impl GrowingTree {
+    pub fn new(policy: SelectionPolicy) -> Self {
+        let [newest, random, oldest] = policy.weights();
+        GrowingTree::with_weights(newest, random, oldest)
+    }
+
+    /// Custom mix of the policies, e.g. `(3, 1, 0)` continues from the newest cell three
+    /// times as often as from a random one. All zero weights fall back to the newest cell.
+    pub fn with_weights(newest: u32, random: u32, oldest: u32) -> Self {
+        GrowingTree {
+            weights: [newest, random, oldest],
+        }
+    }
+
+    fn select(&self, active: usize, rng: &mut impl Rng) -> usize {
+        let total: u32 = self.weights.iter().sum();
+        if total == 0 {
+            return active - 1;
+        }
+
+        let roll = rng.gen_range(0..total);
+        if roll < self.weights[0] {
+            active - 1
+        } else if roll < self.weights[0] + self.weights[1] {
+            rng.gen_range(0..active)
+        } else {
+            0
+        }
+    }
+}
+
+impl MazeGenerationAlgorithm for GrowingTree {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut in_maze = HashSet::from([entrance]);
+        let mut active = vec![entrance];
+        maze.mark_cell_as_path(entrance);
+
+        while !active.is_empty() {
+            let index = self.select(active.len(), &mut rng);
+            let cell = active[index];
+
+            let unvisited: Vec<Point> = cell_neighbors(&maze, cell)
+                .into_iter()
+                .filter(|neighbor| !in_maze.contains(neighbor))
+                .collect();
+            match unvisited.choose(&mut rng) {
+                Some(&next) => {
+                    carve_passage(&mut maze, cell, next);
+                    in_maze.insert(next);
+                    active.push(next);
+                    send_frame(&mut maze, sender)?;
+                }
+                // Keep the order, the oldest cell has to stay first
+                None => {
+                    active.remove(index);
+                }
+            }
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, maze_cells, send_frame, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Hunt-and-kill: walks randomly until it gets stuck, then hunts row by row for an
+/// unvisited cell next to the maze and continues from there. Produces mazes similar to the
+/// backtracker without keeping a stack.
+#[derive(Default)]
+pub struct HuntAndKill;
+
+impl HuntAndKill {
+    pub fn new() -> Self {
+        HuntAndKill
+    }
+}
+
+impl MazeGenerationAlgorithm for HuntAndKill {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let cells = maze_cells(&maze, entrance);
+        let mut visited = HashSet::from([entrance]);
+        maze.mark_cell_as_path(entrance);
+
+        // Cells before `hunt_from` are all visited, so the hunt never rescans them
+        let mut hunt_from = 0;
+        let mut current = Some(entrance);
+        while visited.len() < cells.len() {
+            // Walk
+            while let Some(cell) = current {
+                let unvisited: Vec<Point> = cell_neighbors(&maze, cell)
+                    .into_iter()
+                    .filter(|neighbor| !visited.contains(neighbor))
+                    .collect();
+                current = unvisited.choose(&mut rng).copied();
+                if let Some(next) = current {
+                    carve_passage(&mut maze, cell, next);
+                    visited.insert(next);
+                    send_frame(&mut maze, sender)?;
+                }
+            }
+
+            // Hunt
+            while hunt_from < cells.len() && visited.contains(&cells[hunt_from]) {
+                hunt_from += 1;
+            }
+            for &cell in &cells[hunt_from..] {
+                if visited.contains(&cell) {
+                    continue;
+                }
+
+                let neighbors: Vec<Point> = cell_neighbors(&maze, cell)
+                    .into_iter()
+                    .filter(|neighbor| visited.contains(neighbor))
+                    .collect();
+                if let Some(&neighbor) = neighbors.choose(&mut rng) {
+                    carve_passage(&mut maze, neighbor, cell);
+                    visited.insert(cell);
+                    send_frame(&mut maze, sender)?;
+                    current = Some(cell);
+                    break;
+                }
+            }
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
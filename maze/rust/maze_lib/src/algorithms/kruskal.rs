@@ -0,0 +1,112 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, maze_cells, send_frame, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Union-find over maze cell indices.
+pub(crate) struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl DisjointSet {
+    pub(crate) fn new(size: usize) -> Self {
+        DisjointSet {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+        }
+    }
+
+    pub(crate) fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Path compression
+        let mut current = index;
+        while self.parent[current] != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    /// Joins both sets, returns `false` if they already were the same set.
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+
+        match self.rank[a].cmp(&self.rank[b]) {
+            std::cmp::Ordering::Less => self.parent[a] = b,
+            std::cmp::Ordering::Greater => self.parent[b] = a,
+            std::cmp::Ordering::Equal => {
+                self.parent[b] = a;
+                self.rank[a] += 1;
+            }
+        }
+        true
+    }
+}
+
+/// Randomized Kruskal's algorithm: knocks down walls in random order whenever they
+/// separate two cells which are not connected yet.
+#[derive(Default)]
+pub struct Kruskal;
+
+impl Kruskal {
+    pub fn new() -> Self {
+        Kruskal
+    }
+}
+
+impl MazeGenerationAlgorithm for Kruskal {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let cells = maze_cells(&maze, entrance);
+        for &cell in &cells {
+            maze.mark_cell_as_path(cell);
+        }
+        send_frame(&mut maze, sender)?;
+
+        // Every wall between a cell and its right or bottom neighbour
+        let mut walls: Vec<(Point, Point)> = cells
+            .iter()
+            .flat_map(|&cell| {
+                cell_neighbors(&maze, cell)
+                    .into_iter()
+                    .filter(move |next| next.x > cell.x || next.y > cell.y)
+                    .map(move |next| (cell, next))
+            })
+            .collect();
+        walls.shuffle(&mut rng);
+
+        let mut sets = DisjointSet::new(maze.width * maze.height);
+        for (current, next) in walls {
+            if sets.union(
+                maze.get_index(current.x, current.y),
+                maze.get_index(next.x, next.y),
+            ) {
+                carve_passage(&mut maze, current, next);
+                send_frame(&mut maze, sender)?;
+            }
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
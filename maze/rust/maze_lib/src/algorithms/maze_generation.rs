@@ -1,16 +1,529 @@
-use std::sync::mpsc::Sender;
+use rand::Rng;
 
-use crate::{Maze, MazeType};
+use crate::{CellType, Mask, Maze, MazeType, SlimWallsCellType};
 
-use super::{PathfindingResult, Point};
+use super::{Budget, FrameSink, Movements, PathfindingError, PathfindingResult, Point};
 
 pub trait MazeGenerationAlgorithm {
+    /// Generates a new maze. The same `seed` always produces the same maze.
     fn generate(
         &mut self,
         maze_type: MazeType,
         width: usize,
         height: usize,
         entrance: Point,
-        sender: Option<&Sender<PathfindingResult>>,
-    ) -> Option<Maze>;
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        // Polar mazes take their width from the number of rings
+        let maze = Maze::new(width, height, maze_type, Some(CellType::Wall));
+        check_room(&maze, entrance)?;
+        self.carve(maze, entrance, seed, sender)
+    }
+
+    /// Generates a maze in the shape of `mask`, see `Maze::from_mask`. The entrance is
+    /// the first cell of the mask in reading order.
+    fn generate_masked(
+        &mut self,
+        maze_type: MazeType,
+        mask: &Mask,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let maze = Maze::from_mask(mask, maze_type, Some(CellType::Wall))
+            .ok_or(PathfindingError::InvalidMaze)?;
+        let entrance = *maze_cells(&maze, Point::default())
+            .first()
+            .ok_or(PathfindingError::InvalidMaze)?;
+        self.carve(maze, entrance, seed, sender)
+    }
+
+    /// Carves the passages into `maze`, a grid of wall cells, starting from `entrance`.
+    /// `PathfindingError::InvalidMaze` if the generator cannot handle the maze type or a
+    /// shaped maze.
+    fn carve(
+        &mut self,
+        maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError>;
+
+    /// Like `carve`, but cut off once `budget` runs out. A cut off generator returns `maze`
+    /// with the passages carved so far, `Budget::stop_reason` tells why it stopped.
+    fn carve_within(
+        &mut self,
+        maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+        budget: &Budget,
+    ) -> Result<Maze, PathfindingError> {
+        check_room(&maze, entrance)?;
+
+        let sink = budget.watch(sender, Some(maze.clone()));
+        match self.carve(maze, entrance, seed, Some(&sink)) {
+            Err(PathfindingError::Stopped(_)) => {
+                let mut partial = sink.into_partial().ok_or(PathfindingError::InvalidMaze)?;
+                partial.backup();
+                Ok(partial)
+            }
+            result => result,
+        }
+    }
+}
+
+/// `PathfindingError::InvalidMaze` unless `entrance` is a cell of `maze` and the maze has
+/// another cell for the exit.
+fn check_room(maze: &Maze, entrance: Point) -> Result<(), PathfindingError> {
+    let cells = maze_cells(maze, entrance);
+    if cells.len() < 2 || !cells.contains(&entrance) {
+        return Err(PathfindingError::InvalidMaze);
+    }
+    Ok(())
+}
+
+/// Neighbouring cells a generator can carve into. Thick mazes keep a wall cell between
+/// two cells.
+pub(crate) fn cell_neighbors(maze: &Maze, point: Point) -> Vec<Point> {
+    if maze.maze_type == MazeType::Weave {
+        return weave_neighbors(maze, point);
+    }
+    if maze.maze_type != MazeType::Thick {
+        return maze.neighbors(point);
+    }
+
+    Movements::directions_doubled()
+        .iter()
+        .filter_map(|(dx, dy)| {
+            let x = point.x as i32 + dx;
+            let y = point.y as i32 + dy;
+            maze.is_valid_coord(x, y).then_some(Point {
+                x: x as usize,
+                y: y as usize,
+            })
+        })
+        .collect()
+}
+
+/// Weave mazes can also carve beneath a straight corridor that crosses the way, to the
+/// cell on its far side.
+fn weave_neighbors(maze: &Maze, point: Point) -> Vec<Point> {
+    let mut neighbors = maze.neighbors(point);
+    for (dx, dy) in Movements::directions() {
+        let (x, y) = (point.x as i32, point.y as i32);
+        if !maze.is_valid_coord(x + dx * 2, y + dy * 2) {
+            continue;
+        }
+
+        let crossing = maze.get_cell(Point {
+            x: (x + dx) as usize,
+            y: (y + dy) as usize,
+        });
+        let is_straight_across = [(dx, dy), (-dx, -dy)]
+            .iter()
+            .all(|&direction| crossing.has_wall_in_direction(direction))
+            && [(dy, dx), (-dy, -dx)]
+                .iter()
+                .all(|&direction| !crossing.has_wall_in_direction(direction));
+        if crossing.get_type() != CellType::Wall
+            && !crossing.has_under_passage()
+            && is_straight_across
+        {
+            neighbors.push(Point {
+                x: (x + dx * 2) as usize,
+                y: (y + dy * 2) as usize,
+            });
+        }
+    }
+    neighbors
+}
+
+/// All cells a generator starting from `entrance` has to connect, in row-major order.
+pub(crate) fn maze_cells(maze: &Maze, entrance: Point) -> Vec<Point> {
+    let step = match maze.maze_type {
+        MazeType::Thick => 2,
+        MazeType::Slim
+        | MazeType::Hex
+        | MazeType::Polar
+        | MazeType::Layered(_)
+        | MazeType::Toroidal(_)
+        | MazeType::Weave => 1,
+    };
+
+    (entrance.y % step..maze.height)
+        .step_by(step)
+        .flat_map(|y| {
+            (entrance.x % step..maze.width)
+                .step_by(step)
+                .map(move |x| Point { x, y })
+        })
+        .filter(|point| maze.is_valid_coord(point.x as i32, point.y as i32))
+        .collect()
+}
+
+/// Same cells as `maze_cells`, split into rows.
+pub(crate) fn maze_rows(maze: &Maze, entrance: Point) -> Vec<Vec<Point>> {
+    let mut rows: Vec<Vec<Point>> = Vec::new();
+    for cell in maze_cells(maze, entrance) {
+        match rows.last_mut() {
+            Some(row) if row[0].y == cell.y => row.push(cell),
+            _ => rows.push(vec![cell]),
+        }
+    }
+    rows
+}
+
+/// `PathfindingError::InvalidMaze` unless the cells of `maze` line up in rows and columns.
+/// Rings of a polar maze have different lengths and the rows of a shaped maze have gaps,
+/// and carving row by row never adds stairs between levels or passages over joined edges.
+pub(crate) fn require_row_grid(maze: &Maze) -> Result<(), PathfindingError> {
+    if maze.is_masked()
+        || matches!(
+            maze.maze_type,
+            MazeType::Polar | MazeType::Layered(_) | MazeType::Toroidal(_)
+        )
+    {
+        return Err(PathfindingError::InvalidMaze);
+    }
+    Ok(())
+}
+
+/// Opens the passage between two neighbouring cells.
+pub(crate) fn carve_passage(maze: &mut Maze, current: Point, next: Point) {
+    maze.mark_cell_as_path(current);
+    maze.mark_cell_as_path(next);
+    match maze.maze_type {
+        MazeType::Thick => maze.mark_cell_as_path(Point {
+            x: (current.x + next.x) / 2,
+            y: (current.y + next.y) / 2,
+        }),
+        MazeType::Slim
+        | MazeType::Hex
+        | MazeType::Polar
+        | MazeType::Layered(_)
+        | MazeType::Toroidal(_)
+        | MazeType::Weave => maze.remove_walls_between_cells(current, next),
+    }
+}
+
+/// Sends the cells changed since the last frame. Without a sender the changes are just
+/// dropped.
+pub(crate) fn send_frame(
+    maze: &mut Maze,
+    sender: Option<&dyn FrameSink>,
+) -> Result<(), PathfindingError> {
+    let events = maze.take_changes();
+    match sender {
+        Some(s) => s.accept(PathfindingResult { events }),
+        None => Ok(()),
+    }
+}
+
+/// Random walks take many steps per carved cell, so only every n-th walking step is sent.
+pub(crate) fn walk_frame_interval(cell_count: usize) -> usize {
+    (cell_count / 100).max(1)
+}
+
+/// Places the entrance and a random exit, sends the final frame and backs the maze up.
+pub(crate) fn finish_maze(
+    maze: &mut Maze,
+    entrance: Point,
+    rng: &mut impl Rng,
+    sender: Option<&dyn FrameSink>,
+) -> Result<(), PathfindingError> {
+    maze.mark_cell_as_entrance(entrance);
+    let exit_point = maze
+        .get_random_boundary_point(rng)
+        .ok_or(PathfindingError::InvalidMaze)?;
+    maze.mark_cell_as_exit(exit_point);
+
+    send_frame(maze, sender)?;
+    maze.backup();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::mpsc::channel};
+
+    use super::super::Algorithm;
+    use super::*;
+    use crate::CellType;
+
+    fn is_connected(maze: &Maze, current: Point, next: Point) -> bool {
+        match maze.maze_type {
+            MazeType::Thick => {
+                let between = Point {
+                    x: (current.x + next.x) / 2,
+                    y: (current.y + next.y) / 2,
+                };
+                maze.get_cell(between).get_type() != CellType::Wall
+            }
+            MazeType::Slim
+            | MazeType::Hex
+            | MazeType::Polar
+            | MazeType::Layered(_)
+            | MazeType::Toroidal(_)
+            | MazeType::Weave => maze.is_passable(current, next),
+        }
+    }
+
+    #[test]
+    fn test_generated_mazes_are_perfect() {
+        let entrance = Point::default();
+        for maze_type in MazeType::cell_types() {
+            for algorithm in Algorithm::maze_generation_algorithms(maze_type) {
+                // Caves and dungeons are not carved from cells at all
+                if matches!(algorithm, Algorithm::Cave(_) | Algorithm::Dungeon) {
+                    continue;
+                }
+
+                let maze = algorithm
+                    .get_maze_generator()
+                    .unwrap()
+                    .generate(maze_type, 21, 15, entrance, 3, None)
+                    .unwrap();
+                let cells = maze_cells(&maze, entrance);
+
+                let mut passages = 0;
+                let mut reached = HashSet::from([entrance]);
+                let mut stack = vec![entrance];
+                while let Some(current) = stack.pop() {
+                    for next in cell_neighbors(&maze, current) {
+                        if is_connected(&maze, current, next) {
+                            passages += 1;
+                            if reached.insert(next) {
+                                stack.push(next);
+                            }
+                        }
+                    }
+                }
+
+                // Each passage was counted from both of its ends. WFC keeps the loops its
+                // tiles form, so it is only required to connect every cell.
+                assert_eq!(reached.len(), cells.len(), "{} {}", algorithm, maze_type);
+                if algorithm != Algorithm::WFC {
+                    assert_eq!(passages / 2, cells.len() - 1, "{} {}", algorithm, maze_type);
+                }
+            }
+        }
+    }
+
+    fn generate(algorithm: Algorithm, maze_type: MazeType, seed: u64) -> String {
+        let maze = algorithm
+            .get_maze_generator()
+            .unwrap()
+            .generate(maze_type, 21, 21, Point::default(), seed, None)
+            .unwrap();
+        format!("{:?}", maze)
+    }
+
+    #[test]
+    fn test_masked_mazes_keep_their_shape() {
+        let mask: Mask = "#########\n##.....##\n#.......#\n##.....##\n#########"
+            .parse()
+            .unwrap();
+        for maze_type in [MazeType::Thick, MazeType::Slim, MazeType::Weave] {
+            for algorithm in Algorithm::masked_generation_algorithms(maze_type) {
+                let maze = algorithm
+                    .get_maze_generator()
+                    .unwrap()
+                    .generate_masked(maze_type, &mask, 5, None)
+                    .unwrap();
+
+                let entrance = maze.get_entrance().unwrap();
+                let mut reached = HashSet::from([entrance]);
+                let mut stack = vec![entrance];
+                while let Some(current) = stack.pop() {
+                    for next in cell_neighbors(&maze, current) {
+                        if is_connected(&maze, current, next) && reached.insert(next) {
+                            stack.push(next);
+                        }
+                    }
+                }
+                assert_eq!(reached.len(), mask.count(), "{} {}", algorithm, maze_type);
+
+                // Nothing is carved outside of the mask and the exit lies on its edge
+                let exit = maze.get_exit().unwrap();
+                for y in 0..maze.height {
+                    for x in 0..maze.width {
+                        let cell = maze.get_cell(Point { x, y });
+                        if !maze.is_valid_coord(x as i32, y as i32) {
+                            assert_eq!(cell.get_type(), CellType::Wall);
+                        }
+                    }
+                }
+                assert_ne!(exit, entrance);
+                assert!(maze.is_valid_coord(exit.x as i32, exit.y as i32));
+                assert!(Movements::directions().iter().any(|(dx, dy)| {
+                    !maze.is_valid_coord(exit.x as i32 + dx, exit.y as i32 + dy)
+                }));
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_cell_mask_is_reported() {
+        let mask: Mask = "...\n.#.\n...".parse().unwrap();
+        for maze_type in [MazeType::Slim, MazeType::Weave] {
+            for algorithm in Algorithm::masked_generation_algorithms(maze_type) {
+                let result = algorithm
+                    .get_maze_generator()
+                    .unwrap()
+                    .generate_masked(maze_type, &mask, 5, None);
+                assert_eq!(
+                    result.unwrap_err(),
+                    PathfindingError::InvalidMaze,
+                    "{} {}",
+                    algorithm,
+                    maze_type
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_frames_rebuild_the_maze() {
+        for maze_type in MazeType::cell_types() {
+            for algorithm in Algorithm::maze_generation_algorithms(maze_type) {
+                let (sender, receiver) = channel();
+                let maze = algorithm
+                    .get_maze_generator()
+                    .unwrap()
+                    .generate(maze_type, 21, 15, Point::default(), 3, Some(&sender))
+                    .unwrap();
+                drop(sender);
+
+                let mut copy = Maze::new(21, 15, maze_type, Some(CellType::Wall));
+                for frame in receiver {
+                    frame.apply(&mut copy);
+                }
+                for y in 0..maze.height {
+                    for x in 0..maze.width {
+                        let point = Point { x, y };
+                        assert_eq!(
+                            copy.get_cell(point),
+                            maze.get_cell(point),
+                            "{} {} at {:?}",
+                            algorithm,
+                            maze_type,
+                            point
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exit_differs_from_entrance() {
+        for maze_type in MazeType::cell_types() {
+            for algorithm in Algorithm::maze_generation_algorithms(maze_type) {
+                // Small mazes leave the exit few places to go, caves and dungeons need room
+                let size = match algorithm {
+                    Algorithm::Cave(_) | Algorithm::Dungeon => 21,
+                    _ => 5,
+                };
+                let mut generator = algorithm.get_maze_generator().unwrap();
+                for seed in 0..50 {
+                    let maze = generator
+                        .generate(maze_type, size, size, Point::default(), seed, None)
+                        .unwrap();
+                    let entrance = maze.get_entrance();
+                    let exit = maze.get_exit();
+                    assert!(entrance.is_some(), "{} {} {}", algorithm, maze_type, seed);
+                    assert!(exit.is_some(), "{} {} {}", algorithm, maze_type, seed);
+                    assert_ne!(entrance, exit, "{} {} {}", algorithm, maze_type, seed);
+                    let exit = exit.unwrap();
+                    assert!(maze.is_valid_coord(exit.x as i32, exit.y as i32));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_thick_mazes_keep_their_outer_walls() {
+        for algorithm in Algorithm::maze_generation_algorithms(MazeType::Thick) {
+            // The last row and column of an even sized maze lie outside of its cells
+            let (width, height) = match algorithm {
+                Algorithm::Cave(_) | Algorithm::Dungeon => (22, 20),
+                _ => (10, 8),
+            };
+            let mut generator = algorithm.get_maze_generator().unwrap();
+            for seed in 0..10 {
+                let maze = generator
+                    .generate(MazeType::Thick, width, height, Point::default(), seed, None)
+                    .unwrap();
+                let exit = maze.get_exit();
+                for y in 0..height {
+                    for x in 0..width {
+                        let point = Point { x, y };
+                        if (x == 0 || y == 0 || x == width - 1 || y == height - 1)
+                            && Some(point) != exit
+                        {
+                            assert_eq!(
+                                maze.get_cell(point).get_type(),
+                                CellType::Wall,
+                                "{} {} {:?}",
+                                algorithm,
+                                seed,
+                                point
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_maze_size_is_checked() {
+        let mut generator = Algorithm::Backtracking.get_maze_generator().unwrap();
+        let polar = generator.generate(MazeType::Polar, 0, 5, Point { x: 0, y: 0 }, 1, None);
+        assert!(polar.is_ok());
+        let empty = generator.generate(MazeType::Thick, 0, 0, Point::default(), 1, None);
+        assert_eq!(empty.unwrap_err(), PathfindingError::InvalidMaze);
+        // A single cell leaves no room for the exit
+        let single = generator.generate(MazeType::Thick, 3, 3, Point::default(), 1, None);
+        assert_eq!(single.unwrap_err(), PathfindingError::InvalidMaze);
+
+        // Tiny mazes are generated or reported, but never panic or hang
+        for maze_type in MazeType::cell_types() {
+            for algorithm in Algorithm::maze_generation_algorithms(maze_type) {
+                let mut generator = algorithm.get_maze_generator().unwrap();
+                for (width, height) in [(1, 1), (1, 4), (2, 2), (2, 3), (4, 1), (4, 4)] {
+                    for entrance in [Point::default(), Point { x: 0, y: 0 }] {
+                        let Ok(maze) =
+                            generator.generate(maze_type, width, height, entrance, 1, None)
+                        else {
+                            continue;
+                        };
+                        let exit = maze.get_exit().unwrap();
+                        assert_ne!(maze.get_entrance(), Some(exit), "{}", algorithm);
+                        assert!(maze.is_valid_coord(exit.x as i32, exit.y as i32));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_maze() {
+        for maze_type in MazeType::cell_types() {
+            for algorithm in Algorithm::maze_generation_algorithms(maze_type) {
+                assert_eq!(
+                    generate(algorithm, maze_type, 7),
+                    generate(algorithm, maze_type, 7),
+                    "{} is not reproducible for {} mazes",
+                    algorithm,
+                    maze_type
+                );
+                assert_ne!(
+                    generate(algorithm, maze_type, 7),
+                    generate(algorithm, maze_type, 8)
+                );
+            }
+        }
+    }
 }
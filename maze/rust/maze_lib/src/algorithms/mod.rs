@@ -1,27 +1,55 @@
 mod algorithm;
+mod budget;
 mod directions;
+mod frame_pump;
 mod maze_generation;
 mod pathfinding;
 mod point;
 
+mod aldous_broder;
 mod astar;
 mod backtracking;
 mod bellman_ford;
 mod bfs;
+mod binary_tree;
+mod cave;
 mod dfs;
 mod dijkstra;
-// mod wfc;
+mod dungeon;
+mod eller;
+mod growing_tree;
+mod hunt_and_kill;
+mod kruskal;
+mod prim;
+mod recursive_division;
+mod sidewinder;
+mod wfc;
+mod wilson;
 
 pub use algorithm::*;
+pub use budget::*;
 pub use directions::*;
+pub use frame_pump::*;
 pub use maze_generation::*;
 pub use pathfinding::*;
 pub use point::*;
 
+pub use aldous_broder::*;
 pub use astar::*;
 pub use backtracking::*;
 pub use bellman_ford::*;
 pub use bfs::*;
+pub use binary_tree::*;
+pub use cave::*;
 pub use dfs::*;
 pub use dijkstra::*;
-// pub use wfc::*;
+pub use dungeon::*;
+pub use eller::*;
+pub use growing_tree::*;
+pub use hunt_and_kill::*;
+pub use kruskal::*;
+pub use prim::*;
+pub use recursive_division::*;
+pub use sidewinder::*;
+pub use wfc::*;
+pub use wilson::*;
@@ -1,10 +1,13 @@
-use std::sync::mpsc::Sender;
+use std::{
+    fmt,
+    sync::mpsc::{SendError, Sender, SyncSender},
+};
 
-use crate::maze::Maze;
+use crate::{maze::Maze, CellType, MazeCell};
 
-use super::Algorithm;
+use super::{Algorithm, Budget, Point, StopReason};
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct PathfindingStats {
     pub steps: usize,
 }
@@ -17,13 +20,260 @@ pub enum PathfindingState {
     Finished,
 }
 
+/// A single change made by a generator or a search. Frontends keep their own copy of the
+/// maze and bring it up to date with `Maze::apply`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepEvent {
+    /// The cell took a new type, walls or terrain.
+    CellChanged {
+        point: Point,
+        cell: MazeCell,
+    },
+    /// The cell was put on the frontier of a search, its queue, heap or stack.
+    FrontierPush(Point),
+    /// The cell was taken off the frontier.
+    FrontierPop(Point),
+    /// The search reached the exit along these cells, the entrance comes first.
+    PathFound(Vec<Point>),
+    Stats(PathfindingStats),
+}
+
+/// One frame of an animation, the events since the previous frame.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct PathfindingResult {
-    pub stats: Option<PathfindingStats>,
-    pub maze: Maze,
+    pub events: Vec<StepEvent>,
+}
+
+impl PathfindingResult {
+    /// Applies the cell changes of the frame to `maze`, see `Maze::apply`.
+    pub fn apply(&self, maze: &mut Maze) {
+        for event in &self.events {
+            maze.apply(event);
+        }
+    }
+
+    /// Latest statistics of the frame, if it has any.
+    pub fn stats(&self) -> Option<PathfindingStats> {
+        self.events.iter().rev().find_map(|event| match event {
+            StepEvent::Stats(stats) => Some(*stats),
+            _ => None,
+        })
+    }
+}
+
+/// Why a search or a generator could not run to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathfindingError {
+    MissingEntrance,
+    MissingExit,
+    /// No path leads from the entrance to the exit.
+    Unreachable,
+    /// The receiving end of the frames was dropped.
+    Disconnected,
+    /// The maze has a type, shape or size the algorithm cannot handle, or its entrance or
+    /// exit lies outside of it.
+    InvalidMaze,
+    /// The budget of the run ran out, see `Budget`.
+    Stopped(StopReason),
+    /// The algorithm panicked on the thread of a `FramePump`.
+    Panicked,
+}
+
+impl fmt::Display for PathfindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathfindingError::MissingEntrance => write!(f, "Maze has no entrance"),
+            PathfindingError::MissingExit => write!(f, "Maze has no exit"),
+            PathfindingError::Unreachable => write!(f, "Exit is unreachable"),
+            PathfindingError::Disconnected => write!(f, "Nobody is receiving the frames"),
+            PathfindingError::InvalidMaze => write!(f, "Algorithm cannot handle this maze"),
+            PathfindingError::Stopped(reason) => write!(f, "Stopped, {}", reason),
+            PathfindingError::Panicked => write!(f, "Algorithm crashed"),
+        }
+    }
+}
+
+impl std::error::Error for PathfindingError {}
+
+/// Where an algorithm sends its frames. Returning an error stops the algorithm.
+pub trait FrameSink {
+    fn accept(&self, frame: PathfindingResult) -> Result<(), PathfindingError>;
+
+    /// Called by a search before every cell it expands, whether a frame follows or not.
+    fn expand(&self) -> Result<(), PathfindingError> {
+        Ok(())
+    }
+}
+
+impl FrameSink for Sender<PathfindingResult> {
+    fn accept(&self, frame: PathfindingResult) -> Result<(), PathfindingError> {
+        Ok(self.send(frame)?)
+    }
+}
+
+/// Waits until the frame is received when the channel is full, which keeps an algorithm
+/// from running ahead of its receiver.
+impl FrameSink for SyncSender<PathfindingResult> {
+    fn accept(&self, frame: PathfindingResult) -> Result<(), PathfindingError> {
+        Ok(self.send(frame)?)
+    }
+}
+
+impl From<SendError<PathfindingResult>> for PathfindingError {
+    fn from(_: SendError<PathfindingResult>) -> Self {
+        PathfindingError::Disconnected
+    }
+}
+
+/// Sends the cells of `maze` changed since the last frame, followed by `events`, as the
+/// next frame.
+pub(crate) fn send_step(
+    maze: &mut Maze,
+    sender: &dyn FrameSink,
+    events: Vec<StepEvent>,
+) -> Result<(), PathfindingError> {
+    let mut frame = maze.take_changes();
+    frame.extend(events);
+    sender.accept(PathfindingResult { events: frame })
+}
+
+/// Marks the cells of `path` after the entrance as the final path, a frame each. The
+/// last frame reports the path as found.
+pub(crate) fn send_final_path(
+    maze: &mut Maze,
+    sender: &dyn FrameSink,
+    path: &[Point],
+) -> Result<(), PathfindingError> {
+    for &point in path.iter().skip(1) {
+        maze.mark_cell_as_final_path(point);
+        send_step(maze, sender, Vec::new())?;
+    }
+    send_step(maze, sender, vec![StepEvent::PathFound(path.to_vec())])
+}
+
+/// Entrance and exit of a maze that is about to be searched.
+pub(crate) fn endpoints(maze: &Maze) -> Result<(Point, Point), PathfindingError> {
+    let entrance = maze
+        .get_entrance()
+        .ok_or(PathfindingError::MissingEntrance)?;
+    let exit = maze.get_exit().ok_or(PathfindingError::MissingExit)?;
+    let is_inside = |point: Point| maze.is_valid_coord(point.x as i32, point.y as i32);
+    if !is_inside(entrance) || !is_inside(exit) {
+        return Err(PathfindingError::InvalidMaze);
+    }
+    Ok((entrance, exit))
+}
+
+/// What a search found, returned by `PathfindingAlgorithm::find_path` next to the frames
+/// it sends.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathfindingOutcome {
+    /// Cells from the entrance to the exit, both included. Empty if the exit was not reached.
+    pub path: Vec<Point>,
+    /// Cost of entering every cell of the path after the entrance, see `Maze::get_cost`.
+    pub cost: u32,
+    /// Cells the search expanded, that is looked at the neighbours of.
+    pub expanded: usize,
+    pub reachable: bool,
+    /// How far the search got if its budget ran out, `None` if it ran to the end.
+    pub stopped: Option<PartialSearch>,
+}
+
+/// State of a search that was cut off, see `PathfindingAlgorithm::find_path_within`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSearch {
+    pub reason: StopReason,
+    /// Frames the search sent before the cut
+    pub frames: usize,
+    /// Cells the search had marked as visited or as a part of its path, row by row
+    pub visited: Vec<Point>,
+}
+
+impl PathfindingOutcome {
+    pub fn found(maze: &Maze, path: Vec<Point>, expanded: usize) -> Self {
+        let cost = path.iter().skip(1).map(|&point| maze.get_cost(point)).sum();
+        PathfindingOutcome {
+            path,
+            cost,
+            expanded,
+            reachable: true,
+            stopped: None,
+        }
+    }
+
+    pub fn unreachable(expanded: usize) -> Self {
+        PathfindingOutcome {
+            expanded,
+            ..PathfindingOutcome::default()
+        }
+    }
+
+    /// Outcome of a search cut off after sending `frames` frames and expanding `expanded`
+    /// cells, the cells it marked are read from `maze`.
+    pub fn stopped(reason: StopReason, frames: usize, expanded: usize, maze: &Maze) -> Self {
+        let visited = (0..maze.width * maze.height)
+            .map(|index| Point {
+                x: index % maze.width,
+                y: index / maze.width,
+            })
+            .filter(|&point| {
+                matches!(
+                    maze.get_cell(point).get_type(),
+                    CellType::Visited | CellType::FinalPath
+                )
+            })
+            .collect();
+        PathfindingOutcome {
+            expanded,
+            stopped: Some(PartialSearch {
+                reason,
+                frames,
+                visited,
+            }),
+            ..PathfindingOutcome::default()
+        }
+    }
+
+    /// The path found, `PathfindingError::Unreachable` if there is none.
+    pub fn into_path(self) -> Result<Vec<Point>, PathfindingError> {
+        if self.reachable {
+            Ok(self.path)
+        } else {
+            Err(PathfindingError::Unreachable)
+        }
+    }
 }
 
 pub trait PathfindingAlgorithm {
-    fn find_path(&mut self, maze: &mut Maze, sender: &Sender<PathfindingResult>);
+    /// Searches a path from the entrance to the exit of `maze`. The cells are marked as
+    /// the search goes and every step is sent as a frame. Not finding the exit is not an
+    /// error, the outcome is just not `reachable`.
+    fn find_path(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+    ) -> Result<PathfindingOutcome, PathfindingError>;
+
+    /// Like `find_path`, but cut off once `budget` runs out. The outcome of a cut off
+    /// search has no path, its `stopped` state tells how far it got. The cells of `maze`
+    /// stay marked as well.
+    fn find_path_within(
+        &mut self,
+        maze: &mut Maze,
+        sender: &dyn FrameSink,
+        budget: &Budget,
+    ) -> Result<PathfindingOutcome, PathfindingError> {
+        let sink = budget.watch(Some(sender), None);
+        match self.find_path(maze, &sink) {
+            Err(PathfindingError::Stopped(reason)) => Ok(PathfindingOutcome::stopped(
+                reason,
+                sink.steps(),
+                sink.expansions(),
+                maze,
+            )),
+            result => result,
+        }
+    }
 
     fn get_stats(&self) -> Option<PathfindingStats>;
 
@@ -35,3 +285,118 @@ impl PathfindingStats {
         self.steps += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use super::*;
+    use crate::{CellType, MazeType};
+
+    fn find_path(algorithm: Algorithm, maze: &Maze) -> PathfindingOutcome {
+        let mut maze = maze.clone();
+        let (sender, _receiver) = channel();
+        algorithm
+            .get_pathfinder()
+            .unwrap()
+            .find_path(&mut maze, &sender)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_outcome_of_every_pathfinder() {
+        let maze = Algorithm::Kruskal
+            .get_maze_generator()
+            .unwrap()
+            .generate(MazeType::Thick, 21, 21, Point::default(), 4, None)
+            .unwrap();
+        let (entrance, exit) = (maze.get_entrance().unwrap(), maze.get_exit().unwrap());
+
+        for algorithm in Algorithm::pathfinding_algorithms() {
+            let outcome = find_path(algorithm, &maze);
+            assert!(outcome.reachable, "{}", algorithm);
+            assert_eq!(outcome.path.first(), Some(&entrance), "{}", algorithm);
+            assert_eq!(outcome.path.last(), Some(&exit), "{}", algorithm);
+            // A perfect maze has a single path, every step onto a plain cell costs 1
+            assert_eq!(outcome.path, find_path(Algorithm::BFS, &maze).path);
+            assert_eq!(outcome.cost as usize, outcome.path.len() - 1);
+            assert!(outcome.expanded >= outcome.path.len() - 1, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_frames_replay_the_search() {
+        let maze = Algorithm::Kruskal
+            .get_maze_generator()
+            .unwrap()
+            .generate(MazeType::Thick, 21, 21, Point::default(), 4, None)
+            .unwrap();
+
+        for algorithm in Algorithm::pathfinding_algorithms() {
+            let mut searched = maze.clone();
+            let (sender, receiver) = channel();
+            let outcome = algorithm
+                .get_pathfinder()
+                .unwrap()
+                .find_path(&mut searched, &sender)
+                .unwrap();
+            drop(sender);
+
+            let mut copy = maze.clone();
+            let mut found = None;
+            let mut pushed = false;
+            for frame in receiver {
+                frame.apply(&mut copy);
+                for event in frame.events {
+                    match event {
+                        StepEvent::PathFound(path) => found = Some(path),
+                        StepEvent::FrontierPush(_) => pushed = true,
+                        _ => {}
+                    }
+                }
+            }
+            assert_eq!(found, Some(outcome.path), "{}", algorithm);
+            assert!(pushed, "{}", algorithm);
+            assert_eq!(format!("{:?}", copy), format!("{:?}", searched));
+        }
+    }
+
+    #[test]
+    fn test_outcome_of_unreachable_exit() {
+        let mut maze = Maze::new(7, 3, MazeType::Thick, Some(CellType::Path));
+        maze.mark_cell_as_entrance(Point { x: 1, y: 1 });
+        maze.mark_cell_as_exit(Point { x: 5, y: 1 });
+        for y in 0..3 {
+            maze.mark_cell_as_wall(Point { x: 3, y });
+        }
+        maze.backup();
+
+        for algorithm in Algorithm::pathfinding_algorithms() {
+            let outcome = find_path(algorithm, &maze);
+            assert!(!outcome.reachable, "{}", algorithm);
+            assert!(outcome.path.is_empty());
+            assert!(outcome.expanded > 0);
+        }
+    }
+
+    #[test]
+    fn test_errors_instead_of_panics() {
+        let mut maze = Maze::new(7, 3, MazeType::Thick, Some(CellType::Path));
+        maze.mark_cell_as_entrance(Point { x: 1, y: 1 });
+        maze.backup();
+
+        for algorithm in Algorithm::pathfinding_algorithms() {
+            let mut pathfinder = algorithm.get_pathfinder().unwrap();
+            let (sender, receiver) = channel();
+            let result = pathfinder.find_path(&mut maze.clone(), &sender);
+            assert_eq!(result, Err(PathfindingError::MissingExit), "{}", algorithm);
+
+            let mut reachable = maze.clone();
+            reachable.mark_cell_as_exit(Point { x: 5, y: 1 });
+            reachable.backup();
+            drop(receiver);
+            let result = pathfinder.find_path(&mut reachable, &sender);
+            assert_eq!(result, Err(PathfindingError::Disconnected), "{}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, send_frame, FrameSink, MazeGenerationAlgorithm,
+    PathfindingError, Point,
+};
+
+/// Randomized Prim's algorithm: grows the maze from the entrance by connecting a random
+/// frontier cell to the maze on every step.
+#[derive(Default)]
+pub struct Prim;
+
+impl Prim {
+    pub fn new() -> Self {
+        Prim
+    }
+}
+
+impl MazeGenerationAlgorithm for Prim {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut in_maze = HashSet::from([entrance]);
+        let mut frontier = cell_neighbors(&maze, entrance);
+        let mut in_frontier: HashSet<Point> = frontier.iter().copied().collect();
+        maze.mark_cell_as_path(entrance);
+
+        while !frontier.is_empty() {
+            let cell = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            in_frontier.remove(&cell);
+
+            let neighbors = cell_neighbors(&maze, cell);
+            let connected: Vec<&Point> = neighbors
+                .iter()
+                .filter(|neighbor| in_maze.contains(neighbor))
+                .collect();
+            if let Some(&&neighbor) = connected.choose(&mut rng) {
+                carve_passage(&mut maze, neighbor, cell);
+                send_frame(&mut maze, sender)?;
+            }
+            in_maze.insert(cell);
+
+            for neighbor in neighbors {
+                if !in_maze.contains(&neighbor) && in_frontier.insert(neighbor) {
+                    frontier.push(neighbor);
+                }
+            }
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{maze::Maze, MazeType};
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, maze_cells, send_frame, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Part of the field that is still to be divided, in cells rather than maze coordinates.
+#[derive(Clone, Copy)]
+struct Region {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Recursive division. Starts from an open field and splits it with a wall that has a
+/// single gap, then keeps dividing both halves until they are one cell wide. Square grids
+/// only, nothing is generated for hexagonal and polar mazes.
+#[derive(Default)]
+pub struct RecursiveDivision;
+
+impl RecursiveDivision {
+    pub fn new() -> Self {
+        RecursiveDivision
+    }
+
+    fn add_wall(maze: &mut Maze, current: Point, next: Point) {
+        match maze.maze_type {
+            MazeType::Thick => maze.mark_cell_as_wall(Point {
+                x: (current.x + next.x) / 2,
+                y: (current.y + next.y) / 2,
+            }),
+            MazeType::Slim
+            | MazeType::Hex
+            | MazeType::Polar
+            | MazeType::Layered(_)
+            | MazeType::Toroidal(_)
+            | MazeType::Weave => maze.add_walls_between_cells(current, next),
+        }
+    }
+
+    /// Thick mazes have a corner cell between four cells, it belongs to the wall as well.
+    fn set_corner(maze: &mut Maze, current: Point, diagonal: Point, wall: bool) {
+        if maze.maze_type == MazeType::Thick {
+            let corner = Point {
+                x: (current.x + diagonal.x) / 2,
+                y: (current.y + diagonal.y) / 2,
+            };
+            if wall {
+                maze.mark_cell_as_wall(corner);
+            } else {
+                maze.mark_cell_as_path(corner);
+            }
+        }
+    }
+
+    fn divide(
+        maze: &mut Maze,
+        columns: &[usize],
+        rows: &[usize],
+        region: Region,
+        rng: &mut impl Rng,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<(), PathfindingError> {
+        if region.width < 2 || region.height < 2 {
+            return Ok(());
+        }
+
+        let horizontal = match region.width.cmp(&region.height) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => rng.gen_bool(0.5),
+        };
+        let point = |x: usize, y: usize| Point {
+            x: columns[x],
+            y: rows[y],
+        };
+
+        let (first, second) = if horizontal {
+            // Wall below row `wall` with a gap in column `gap`
+            let wall = rng.gen_range(region.y..region.y + region.height - 1);
+            let gap = rng.gen_range(region.x..region.x + region.width);
+            for x in region.x..region.x + region.width {
+                if x != gap {
+                    RecursiveDivision::add_wall(maze, point(x, wall), point(x, wall + 1));
+                }
+                if x + 1 < region.x + region.width {
+                    let diagonal = point(x + 1, wall + 1);
+                    RecursiveDivision::set_corner(maze, point(x, wall), diagonal, true);
+                }
+            }
+
+            let top = wall + 1 - region.y;
+            (
+                Region {
+                    height: top,
+                    ..region
+                },
+                Region {
+                    y: wall + 1,
+                    height: region.height - top,
+                    ..region
+                },
+            )
+        } else {
+            // Wall right of column `wall` with a gap in row `gap`
+            let wall = rng.gen_range(region.x..region.x + region.width - 1);
+            let gap = rng.gen_range(region.y..region.y + region.height);
+            for y in region.y..region.y + region.height {
+                if y != gap {
+                    RecursiveDivision::add_wall(maze, point(wall, y), point(wall + 1, y));
+                }
+                if y + 1 < region.y + region.height {
+                    let diagonal = point(wall + 1, y + 1);
+                    RecursiveDivision::set_corner(maze, point(wall, y), diagonal, true);
+                }
+            }
+
+            let left = wall + 1 - region.x;
+            (
+                Region {
+                    width: left,
+                    ..region
+                },
+                Region {
+                    x: wall + 1,
+                    width: region.width - left,
+                    ..region
+                },
+            )
+        };
+        send_frame(maze, sender)?;
+
+        RecursiveDivision::divide(maze, columns, rows, first, rng, sender)?;
+        RecursiveDivision::divide(maze, columns, rows, second, rng, sender)
+    }
+}
+
+impl MazeGenerationAlgorithm for RecursiveDivision {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        // Straight walls cannot separate hexagonal cells, they touch diagonally as well, the
+        // levels of a layered maze are joined by stairs rather than split by walls, a wall
+        // across a toroidal maze leaves both halves joined over the wrapped edge and the gap
+        // in a wall across a shaped maze may fall outside of it
+        if maze.is_masked() || !matches!(maze.maze_type, MazeType::Thick | MazeType::Slim) {
+            return Err(PathfindingError::InvalidMaze);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let cells = maze_cells(&maze, entrance);
+        let first = *cells.first().ok_or(PathfindingError::InvalidMaze)?;
+        let columns: Vec<usize> = cells
+            .iter()
+            .filter(|cell| cell.y == first.y)
+            .map(|cell| cell.x)
+            .collect();
+        let rows: Vec<usize> = cells
+            .iter()
+            .filter(|cell| cell.x == first.x)
+            .map(|cell| cell.y)
+            .collect();
+
+        // Open field, every cell is connected to all of its neighbours
+        for &cell in &cells {
+            for next in cell_neighbors(&maze, cell) {
+                carve_passage(&mut maze, cell, next);
+            }
+        }
+        for x in 0..columns.len().saturating_sub(1) {
+            for y in 0..rows.len().saturating_sub(1) {
+                let current = Point {
+                    x: columns[x],
+                    y: rows[y],
+                };
+                let diagonal = Point {
+                    x: columns[x + 1],
+                    y: rows[y + 1],
+                };
+                RecursiveDivision::set_corner(&mut maze, current, diagonal, false);
+            }
+        }
+        send_frame(&mut maze, sender)?;
+
+        let field = Region {
+            x: 0,
+            y: 0,
+            width: columns.len(),
+            height: rows.len(),
+        };
+        RecursiveDivision::divide(&mut maze, &columns, &rows, field, &mut rng, sender)?;
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
@@ -0,0 +1,56 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::maze::Maze;
+
+use super::{
+    carve_passage, finish_maze, maze_rows, require_row_grid, send_frame, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Sidewinder: carves each row in runs of cells going right, every run is then joined to
+/// the row above through one of its cells. The top row is a single corridor and every
+/// path only ever goes up once per row.
+#[derive(Default)]
+pub struct Sidewinder;
+
+impl Sidewinder {
+    pub fn new() -> Self {
+        Sidewinder
+    }
+}
+
+impl MazeGenerationAlgorithm for Sidewinder {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        require_row_grid(&maze)?;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let rows = maze_rows(&maze, entrance);
+        for (y, row) in rows.iter().enumerate() {
+            let mut run_start = 0;
+            for (x, &cell) in row.iter().enumerate() {
+                maze.mark_cell_as_path(cell);
+
+                // The top row has nothing above it, so it is one long run
+                let end_run = y > 0 && (x + 1 == row.len() || rng.gen_bool(0.5));
+                if end_run {
+                    let up = rng.gen_range(run_start..=x);
+                    carve_passage(&mut maze, row[up], rows[y - 1][up]);
+                    run_start = x + 1;
+                } else if x + 1 < row.len() {
+                    carve_passage(&mut maze, cell, row[x + 1]);
+                }
+            }
+            send_frame(&mut maze, sender)?;
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
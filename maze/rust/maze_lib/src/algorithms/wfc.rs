@@ -1,384 +1,316 @@
-use super::{MazeGenerationAlgorithm, Point, MOVEMENTS};
-use crate::maze::Maze;
-use crate::ThickMazeCell;
-use rand::prelude::SliceRandom;
-use rand::Rng;
-use std::collections::HashMap;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
-#[derive(Default)]
-pub struct WFC;
-
-impl WFC {
-    pub fn new() -> Self {
-        WFC
-    }
+use crate::{maze::Maze, MazeType};
 
-    fn get_valid_neighbors(&self, maze: &Maze, x: usize, y: usize) -> Vec<Point> {
-        let mut neighbors = Vec::new();
+use super::{
+    carve_passage, finish_maze, maze_cells, require_row_grid, send_frame, DisjointSet, FrameSink,
+    MazeGenerationAlgorithm, PathfindingError, Point,
+};
 
-        for (dx, dy) in MOVEMENTS {
-            let new_x = x as i32 + dx;
-            let new_y = y as i32 + dy;
+// A tile is the set of sides a cell is open to, so there are 16 of them
+const UP: u8 = 0b0001;
+const RIGHT: u8 = 0b0010;
+const DOWN: u8 = 0b0100;
+const LEFT: u8 = 0b1000;
 
-            if maze.is_valid_move(new_x, new_y) {
-                neighbors.push(Point {
-                    x: new_x as usize,
-                    y: new_y as usize,
-                });
-            }
-        }
-        neighbors
-    }
+const SIDES: [(u8, i32, i32); 4] = [(UP, 0, -1), (RIGHT, 1, 0), (DOWN, 0, 1), (LEFT, -1, 0)];
 
-    fn calculate_entropy(
-        &self,
-        point: Point,
-        maze: &Maze,
-        collapsed: &HashMap<Point, bool>,
-    ) -> usize {
-        if collapsed.contains_key(&point) {
-            return 0;
-        }
+fn opposite(side: u8) -> u8 {
+    ((side << 2) | (side >> 2)) & 0b1111
+}
 
-        // Calculate entropy based on neighboring constraints
-        let neighbors = self.get_valid_neighbors(maze, point.x, point.y);
-        let path_neighbors = neighbors
-            .iter()
-            .filter(|p| maze.get_cell(p.x, p.y) == ThickMazeCell::Path)
-            .count();
-
-        // Favor paths over walls
-        if path_neighbors < 3 {
-            2 // Can be either path or wall
-        } else {
-            3 // Should be wall to avoid loops
-        }
-    }
+/// Domain with every tile that is open to `side`.
+fn tiles_open_to(side: u8) -> u16 {
+    (0..16u16)
+        .filter(|tile| tile & side as u16 != 0)
+        .fold(0, |domain, tile| domain | 1 << tile)
+}
 
-    fn _collapse_cell(
-        &self,
-        point: Point,
-        maze: &Maze,
-        _collapsed: &HashMap<Point, bool>,
-        rng: &mut impl Rng,
-    ) -> ThickMazeCell {
-        let neighbors = self.get_valid_neighbors(maze, point.x, point.y);
-        let path_neighbors = neighbors
-            .iter()
-            .filter(|p| maze.get_cell(p.x, p.y) == ThickMazeCell::Path)
-            .count();
-
-        // Enforce maze-like structure
-        if path_neighbors >= 2 {
-            ThickMazeCell::Wall
-        } else {
-            // Randomly choose between path and wall, with higher probability of walls
-            if rng.gen_bool(0.3) {
-                ThickMazeCell::Path
-            } else {
-                ThickMazeCell::Wall
-            }
-        }
+/// How likely a tile is picked. Corridors and corners are favoured so the result reads as
+/// a maze, crossings are rare and closed cells are never allowed.
+fn tile_weight(tile: u8) -> f64 {
+    match tile.count_ones() {
+        1 => 2.0,
+        2 => 6.0,
+        3 => 1.5,
+        4 => 0.5,
+        _ => 0.0,
     }
 }
 
-impl MazeGenerationAlgorithm for WFC {
-    fn generate(
-        &mut self,
-        width: usize,
-        height: usize,
-        start_x: usize,
-        start_y: usize,
-    ) -> Option<Maze> {
-        let mut maze = Maze::new(width, height);
-        let mut rng = rand::thread_rng();
-        let mut collapsed: HashMap<Point, bool> = HashMap::new();
-
-        // Create borders
-        for x in 0..width {
-            maze.set_cell(x, 0, ThickMazeCell::Wall);
-            maze.set_cell(x, height - 1, ThickMazeCell::Wall);
-            collapsed.insert(Point { x, y: 0 }, true);
-            collapsed.insert(Point { x, y: height - 1 }, true);
-        }
-        for y in 0..height {
-            maze.set_cell(0, y, ThickMazeCell::Wall);
-            maze.set_cell(width - 1, y, ThickMazeCell::Wall);
-            collapsed.insert(Point { x: 0, y }, true);
-            collapsed.insert(Point { x: width - 1, y }, true);
-        }
+/// Remaining tiles of every cell, the cells are laid out row by row.
+struct Wave {
+    columns: usize,
+    rows: usize,
+    domains: Vec<u16>,
+    /// Previous domains of every change, so a contradiction can be undone
+    trail: Vec<(usize, u16)>,
+    /// Cells whose domain changed since they were last drawn
+    touched: Vec<usize>,
+}
 
-        // Set initial path at start point and mark as entrance
-        let start = Point {
-            x: start_x,
-            y: start_y,
+impl Wave {
+    fn new(columns: usize, rows: usize) -> Self {
+        let mut wave = Wave {
+            columns,
+            rows,
+            domains: Vec::new(),
+            trail: Vec::new(),
+            touched: Vec::new(),
         };
-        maze.set_cell(start_x, start_y, ThickMazeCell::Entrance);
-        collapsed.insert(start, true);
-
-        // Create initial paths around start point to ensure connectivity
-        let mut path_count = 0;
-        for (dx, dy) in MOVEMENTS {
-            let new_x = (start_x as i32 + dx) as usize;
-            let new_y = (start_y as i32 + dy) as usize;
-            if maze.is_valid_move(new_x as i32, new_y as i32)
-                && !self.is_border(&maze, new_x, new_y)
-                && path_count < 2
-            // Limit to 2 initial paths
-            {
-                maze.mark_cell_as_path(Point { x: new_x, y: new_y });
-                collapsed.insert(Point { x: new_x, y: new_y }, true);
-                path_count += 1;
-            }
-        }
-
-        let max_iterations = width * height * 2;
-        let mut iterations = 0;
-
-        // Main wave function collapse loop
-        while collapsed.len() < width * height && iterations < max_iterations {
-            iterations += 1;
-
-            // Find cell with minimum entropy
-            let mut min_entropy = usize::MAX;
-            let mut min_point = None;
-
-            for y in 0..height {
-                for x in 0..width {
-                    let point = Point { x, y };
-                    if !collapsed.contains_key(&point) {
-                        let entropy = self.calculate_entropy(point, &maze, &collapsed);
-                        if entropy > 0 && entropy < min_entropy {
-                            min_entropy = entropy;
-                            min_point = Some(point);
-                        }
-                    }
-                }
-            }
 
-            if let Some(point) = min_point {
-                let neighbors = self.get_valid_neighbors(&maze, point.x, point.y);
-                let path_neighbors = neighbors
+        // Cells on the edge cannot be open towards the outside, a lone cell stays closed
+        wave.domains = (0..columns * rows)
+            .map(|index| {
+                SIDES
                     .iter()
-                    .filter(|p| maze.get_cell(p.x, p.y) == ThickMazeCell::Path)
-                    .count();
-
-                // Increase path probability significantly
-                let path_probability = match path_neighbors {
-                    0 => 0.7, // High probability if no path neighbors
-                    1 => 0.8, // Higher probability with one path neighbor
-                    2 => 0.4, // Medium probability with two path neighbors
-                    _ => 0.2, // Lower probability with more path neighbors
-                };
+                    .filter(|(_, dx, dy)| wave.neighbor(index, *dx, *dy).is_none())
+                    .fold(0xFFFE, |domain, (side, _, _)| {
+                        domain & !tiles_open_to(*side)
+                    })
+                    .max(1)
+            })
+            .collect();
+        wave
+    }
+
+    fn neighbor(&self, index: usize, dx: i32, dy: i32) -> Option<usize> {
+        let x = (index % self.columns) as i32 + dx;
+        let y = (index / self.columns) as i32 + dy;
+        (x >= 0 && y >= 0 && x < self.columns as i32 && y < self.rows as i32)
+            .then(|| y as usize * self.columns + x as usize)
+    }
 
-                let cell_type = if rng.gen_bool(path_probability) {
-                    ThickMazeCell::Path
-                } else {
-                    ThickMazeCell::Wall
+    fn tile(&self, index: usize) -> Option<u8> {
+        let domain = self.domains[index];
+        (domain.count_ones() == 1).then(|| domain.trailing_zeros() as u8)
+    }
+
+    /// Shrinks the domain of a cell, returns `false` if no tile is left.
+    fn restrict(&mut self, index: usize, domain: u16) -> bool {
+        let previous = self.domains[index];
+        if previous & domain != previous {
+            self.trail.push((index, previous));
+            self.touched.push(index);
+            self.domains[index] = previous & domain;
+        }
+        self.domains[index] != 0
+    }
+
+    /// Removes every tile that no longer fits its neighbours, starting from `index`.
+    fn propagate(&mut self, index: usize) -> bool {
+        let mut queue = vec![index];
+        while let Some(current) = queue.pop() {
+            let domain = self.domains[current];
+            for &(side, dx, dy) in SIDES.iter() {
+                let Some(neighbor) = self.neighbor(current, dx, dy) else {
+                    continue;
                 };
 
-                maze.set_cell(point.x, point.y, cell_type);
-                collapsed.insert(point, true);
-
-                // Improve connectivity logic
-                if cell_type == ThickMazeCell::Path && path_neighbors == 0 {
-                    let mut shuffled_neighbors = neighbors.clone();
-                    shuffled_neighbors.shuffle(&mut rng);
-
-                    for neighbor in shuffled_neighbors {
-                        if maze.get_cell(neighbor.x, neighbor.y) == ThickMazeCell::Wall
-                            && !self.is_border(&maze, neighbor.x, neighbor.y)
-                        {
-                            maze.set_cell(neighbor.x, neighbor.y, ThickMazeCell::Path);
-                            collapsed.insert(neighbor, true);
-                            break;
-                        }
-                    }
+                // The neighbour has to be open exactly when this cell is open towards it
+                let open = tiles_open_to(side);
+                let facing = tiles_open_to(opposite(side));
+                let mut allowed = 0xFFFF;
+                if domain & open == 0 {
+                    allowed &= !facing;
                 }
-            } else {
-                // If we can't find a cell to collapse, mark remaining as paths
-                for y in 1..height - 1 {
-                    for x in 1..width - 1 {
-                        let point = Point { x, y };
-                        if !collapsed.contains_key(&point) {
-                            maze.set_cell(x, y, ThickMazeCell::Path);
-                            collapsed.insert(point, true);
-                        }
-                    }
+                if domain & !open == 0 {
+                    allowed &= facing;
                 }
-                break;
-            }
-        }
 
-        // Improved exit point selection
-        let mut best_exit = None;
-        let mut max_distance = 0.0;
-        let mut best_path_count = 0;
-
-        // Check all border points
-        for x in 0..width {
-            for y in [0, height - 1] {
-                if self.has_path_neighbor(&maze, x, y) {
-                    let distance = ((x as i32 - start_x as i32).pow(2)
-                        + (y as i32 - start_y as i32).pow(2))
-                        as f64;
-                    let path_count = self.count_path_neighbors(&maze, x, y);
-
-                    // Prioritize points with more path neighbors
-                    let score = distance * (path_count as f64 + 1.0);
-                    if score > max_distance {
-                        max_distance = score;
-                        best_exit = Some((x, y));
-                        best_path_count = path_count;
-                    }
+                let before = self.domains[neighbor];
+                if !self.restrict(neighbor, allowed) {
+                    return false;
                 }
-            }
-        }
-        for y in 1..height - 1 {
-            for x in [0, width - 1] {
-                if self.has_path_neighbor(&maze, x, y) {
-                    let distance = ((x as i32 - start_x as i32).pow(2)
-                        + (y as i32 - start_y as i32).pow(2))
-                        as f64;
-                    let path_count = self.count_path_neighbors(&maze, x, y);
-
-                    let score = distance * (path_count as f64 + 1.0);
-                    if score > max_distance {
-                        max_distance = score;
-                        best_exit = Some((x, y));
-                        best_path_count = path_count;
-                    }
+                if self.domains[neighbor] != before {
+                    queue.push(neighbor);
                 }
             }
         }
+        true
+    }
 
-        // If no good exit found or path count is too low, create a new path
-        if best_path_count < 2 {
-            if let Some((x, y)) = best_exit {
-                // Create additional paths around the exit
-                for (dx, dy) in MOVEMENTS {
-                    let new_x = (x as i32 + dx) as usize;
-                    let new_y = (y as i32 + dy) as usize;
-                    if maze.is_valid_move(new_x as i32, new_y as i32)
-                        && !self.is_border(&maze, new_x, new_y)
-                    {
-                        maze.set_cell(new_x, new_y, ThickMazeCell::Path);
-                    }
-                }
-            }
+    fn undo(&mut self, trail_len: usize) {
+        while self.trail.len() > trail_len {
+            let (index, domain) = self.trail.pop().unwrap();
+            self.touched.push(index);
+            self.domains[index] = domain;
         }
+    }
 
-        let exit_point = best_exit.unwrap_or_else(|| {
-            // If no exit found, create a path to a border
-            let border_points = vec![
-                (width - 2, height - 1),
-                (width - 1, height - 2),
-                (1, 0),
-                (0, 1),
-            ];
-            let chosen = border_points.choose(&mut rng).unwrap();
-            maze.set_cell(chosen.0, chosen.1, ThickMazeCell::Path);
-            *chosen
-        });
-
-        maze.set_cell(exit_point.0, exit_point.1, ThickMazeCell::Exit);
-
-        // After setting exit point, ensure there's a path from entrance to exit
-        if let Some(path) = self.find_path_to_entrance(&mut maze, exit_point, (start_x, start_y)) {
-            for &(x, y) in &path {
-                if maze.get_cell(x, y) != ThickMazeCell::Entrance
-                    && maze.get_cell(x, y) != ThickMazeCell::Exit
-                {
-                    maze.set_cell(x, y, ThickMazeCell::Path);
-                }
-            }
-        }
+    /// Undecided cell with the lowest Shannon entropy, ties are broken randomly.
+    fn lowest_entropy(&self, rng: &mut impl Rng) -> Option<usize> {
+        (0..self.domains.len())
+            .filter(|&index| self.domains[index].count_ones() > 1)
+            .map(|index| {
+                let weights = (0..16u8)
+                    .filter(|tile| self.domains[index] & 1 << tile != 0)
+                    .map(tile_weight);
+                let (sum, sum_log) = weights.fold((0.0, 0.0), |(sum, sum_log), weight: f64| {
+                    (sum + weight, sum_log + weight * weight.ln())
+                });
+                let entropy = sum.ln() - sum_log / sum + rng.gen::<f64>() * 1e-6;
+                (index, entropy)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
 
-        maze.backup();
-        Some(maze)
+    fn choose_tile(&self, index: usize, rng: &mut impl Rng) -> u8 {
+        let tiles: Vec<u8> = (0..16)
+            .filter(|tile| self.domains[index] & 1 << tile != 0)
+            .collect();
+        *tiles
+            .choose_weighted(rng, |&tile| tile_weight(tile))
+            .unwrap()
     }
 }
 
+/// Wave Function Collapse over tiles describing which sides of a cell are open. Neighbouring
+/// tiles have to agree on the side they share, separate regions are joined at the end so
+/// the entrance always reaches the exit.
+#[derive(Default)]
+pub struct WFC;
+
 impl WFC {
-    // Add helper methods
-    fn is_border(&self, maze: &Maze, x: usize, y: usize) -> bool {
-        x == 0 || y == 0 || x == maze.width - 1 || y == maze.height - 1
+    pub fn new() -> Self {
+        WFC
     }
 
-    fn has_path_neighbor(&self, maze: &Maze, x: usize, y: usize) -> bool {
-        for (dx, dy) in MOVEMENTS {
-            let new_x = x as i32 + dx;
-            let new_y = y as i32 + dy;
-            if maze.is_valid_move(new_x, new_y) {
-                let cell = maze.get_cell(new_x as usize, new_y as usize);
-                if cell == ThickMazeCell::Path {
-                    return true;
-                }
+    /// Brings a cell and the passages around it up to date with the wave. Undecided cells
+    /// are walls, a passage is open if both cells are decided and open towards each other.
+    fn draw(maze: &mut Maze, wave: &Wave, cells: &[Point], index: usize) {
+        let point = cells[index];
+        let tile = wave.tile(index);
+        match tile {
+            Some(_) => maze.mark_cell_as_path(point),
+            None => maze.mark_cell_as_wall(point),
+        }
+
+        for &(side, dx, dy) in SIDES.iter() {
+            let Some(neighbor) = wave.neighbor(index, dx, dy) else {
+                continue;
+            };
+            let next = cells[neighbor];
+            match (tile, wave.tile(neighbor)) {
+                (Some(tile), Some(_)) if tile & side != 0 => carve_passage(maze, point, next),
+                _ if maze.maze_type == MazeType::Thick => maze.mark_cell_as_wall(Point {
+                    x: (point.x + next.x) / 2,
+                    y: (point.y + next.y) / 2,
+                }),
+                _ => maze.add_walls_between_cells(point, next),
             }
         }
-        false
     }
 
-    fn count_path_neighbors(&self, maze: &Maze, x: usize, y: usize) -> usize {
-        MOVEMENTS
-            .iter()
-            .filter(|(dx, dy)| {
-                let new_x = x as i32 + dx;
-                let new_y = y as i32 + dy;
-                maze.is_valid_move(new_x, new_y)
-                    && maze.get_cell(new_x as usize, new_y as usize) == ThickMazeCell::Path
-            })
-            .count()
+    /// Draws every cell touched since the last call.
+    fn draw_touched(maze: &mut Maze, wave: &mut Wave, cells: &[Point]) {
+        let mut touched = std::mem::take(&mut wave.touched);
+        touched.sort_unstable();
+        touched.dedup();
+        for index in touched {
+            WFC::draw(maze, wave, cells, index);
+        }
     }
 
-    // Add helper method to find path to entrance
-    fn find_path_to_entrance(
-        &self,
-        maze: &mut Maze,
-        start: (usize, usize),
-        end: (usize, usize),
-    ) -> Option<Vec<(usize, usize)>> {
-        use std::collections::VecDeque;
-
-        let mut queue = VecDeque::new();
-        let mut visited = HashMap::new();
-
-        queue.push_back(start);
-        visited.insert(start, start);
-
-        while let Some(current) = queue.pop_front() {
-            if current == end {
-                // Reconstruct path
-                let mut path = vec![current];
-                let mut pos = current;
-                while pos != start {
-                    pos = visited[&pos];
-                    path.push(pos);
+    /// Opens walls between regions that ended up disconnected. Both tiles gain a side, so
+    /// neighbouring tiles still agree.
+    fn connect_regions(wave: &mut Wave, rng: &mut impl Rng) {
+        let mut sets = DisjointSet::new(wave.domains.len());
+        let mut closed = Vec::new();
+        for index in 0..wave.domains.len() {
+            let tile = wave.tile(index).unwrap();
+            for &(side, dx, dy) in &SIDES[1..3] {
+                if let Some(neighbor) = wave.neighbor(index, dx, dy) {
+                    if tile & side != 0 {
+                        sets.union(index, neighbor);
+                    } else {
+                        closed.push((index, neighbor, side));
+                    }
                 }
-                path.reverse();
-                return Some(path);
             }
+        }
 
-            for (dx, dy) in MOVEMENTS {
-                let new_x = current.0 as i32 + dx;
-                let new_y = current.1 as i32 + dy;
-
-                if maze.is_valid_move(new_x, new_y) {
-                    let next = (new_x as usize, new_y as usize);
-                    let cell = maze.get_cell(next.0, next.1);
-
-                    if (cell == ThickMazeCell::Path
-                        || cell == ThickMazeCell::Entrance
-                        || cell == ThickMazeCell::Exit)
-                        && !visited.contains_key(&next)
-                    {
-                        visited.insert(next, current);
-                        queue.push_back(next);
-                    }
-                }
+        closed.shuffle(rng);
+        for (index, neighbor, side) in closed {
+            if sets.union(index, neighbor) {
+                let tile = wave.tile(index).unwrap() | side;
+                wave.domains[index] = 1 << tile;
+                let tile = wave.tile(neighbor).unwrap() | opposite(side);
+                wave.domains[neighbor] = 1 << tile;
+                wave.touched.extend([index, neighbor]);
+            }
+        }
+    }
+}
+
+impl MazeGenerationAlgorithm for WFC {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        require_row_grid(&maze)?;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let cells = maze_cells(&maze, entrance);
+        let first = *cells.first().ok_or(PathfindingError::InvalidMaze)?;
+        let columns = cells.iter().filter(|cell| cell.y == first.y).count();
+        let mut wave = Wave::new(columns, cells.len() / columns);
+
+        // Every decision remembers where its changes start on the trail
+        let mut decisions: Vec<(usize, usize, u8)> = Vec::new();
+        while let Some(index) = wave.lowest_entropy(&mut rng) {
+            let tile = wave.choose_tile(index, &mut rng);
+            decisions.push((wave.trail.len(), index, tile));
+
+            let mut consistent = wave.restrict(index, 1 << tile) && wave.propagate(index);
+            while !consistent {
+                // Contradiction, undo the last decision and rule its tile out
+                let (trail_len, index, tile) =
+                    decisions.pop().ok_or(PathfindingError::InvalidMaze)?;
+                wave.undo(trail_len);
+                consistent = wave.restrict(index, !(1 << tile)) && wave.propagate(index);
+            }
+
+            // Only the cells the decision touched are drawn again
+            if sender.is_some() {
+                WFC::draw_touched(&mut maze, &mut wave, &cells);
+                send_frame(&mut maze, sender)?;
+            } else {
+                wave.touched.clear();
             }
         }
 
-        None
+        WFC::connect_regions(&mut wave, &mut rng);
+
+        wave.touched = (0..cells.len()).collect();
+        WFC::draw_touched(&mut maze, &mut wave, &cells);
+        send_frame(&mut maze, sender)?;
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_propagate_detects_contradiction() {
+        let mut wave = Wave::new(3, 3);
+
+        // Close every side facing the centre cell, which cannot be closed on all sides
+        assert!(wave.restrict(1, !tiles_open_to(DOWN)));
+        assert!(wave.restrict(3, !tiles_open_to(RIGHT)));
+        assert!(wave.restrict(5, !tiles_open_to(LEFT)));
+        assert!(wave.restrict(7, !tiles_open_to(UP)));
+        assert!(!(wave.propagate(1) && wave.propagate(3) && wave.propagate(5)));
+
+        wave.undo(0);
+        assert!(wave.domains.iter().all(|&domain| domain.count_ones() > 1));
     }
 }
@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{maze::Maze, MazeType};
+
+use super::{
+    carve_passage, cell_neighbors, finish_maze, maze_cells, send_frame, walk_frame_interval,
+    FrameSink, MazeGenerationAlgorithm, PathfindingError, Point,
+};
+
+/// Wilson's algorithm: adds loop-erased random walks to the maze until every cell is
+/// part of it. Produces a uniform spanning tree, so every possible maze is equally likely.
+#[derive(Default)]
+pub struct Wilson;
+
+impl Wilson {
+    pub fn new() -> Self {
+        Wilson
+    }
+
+    /// Marks a step of the current walk, or clears it again when the walk loop is erased.
+    fn mark_step(maze: &mut Maze, current: Point, next: Point, on_walk: bool) {
+        let mut points = vec![next];
+        if maze.maze_type == MazeType::Thick {
+            points.push(Point {
+                x: (current.x + next.x) / 2,
+                y: (current.y + next.y) / 2,
+            });
+        }
+
+        for point in points {
+            if on_walk {
+                maze.mark_cell_as_visited(point);
+            } else {
+                maze.mark_cell_as_wall(point);
+            }
+        }
+    }
+}
+
+impl MazeGenerationAlgorithm for Wilson {
+    fn carve(
+        &mut self,
+        mut maze: Maze,
+        entrance: Point,
+        seed: u64,
+        sender: Option<&dyn FrameSink>,
+    ) -> Result<Maze, PathfindingError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut cells = maze_cells(&maze, entrance);
+        let frame_interval = walk_frame_interval(cells.len());
+        cells.shuffle(&mut rng);
+
+        let mut in_maze = HashSet::from([entrance]);
+        maze.mark_cell_as_path(entrance);
+        send_frame(&mut maze, sender)?;
+
+        let mut steps = 0;
+        for start in cells {
+            if in_maze.contains(&start) {
+                continue;
+            }
+
+            // Walk randomly until the maze is hit, erasing every loop the walk makes
+            let mut walk = vec![start];
+            let mut positions = HashMap::from([(start, 0)]);
+            maze.mark_cell_as_visited(start);
+
+            loop {
+                let current = *walk.last().unwrap();
+                let next = *cell_neighbors(&maze, current)
+                    .choose(&mut rng)
+                    .ok_or(PathfindingError::InvalidMaze)?;
+
+                if in_maze.contains(&next) {
+                    walk.push(next);
+                    break;
+                }
+
+                if let Some(&position) = positions.get(&next) {
+                    for index in (position + 1..walk.len()).rev() {
+                        Wilson::mark_step(&mut maze, walk[index - 1], walk[index], false);
+                        positions.remove(&walk[index]);
+                    }
+                    walk.truncate(position + 1);
+                } else {
+                    Wilson::mark_step(&mut maze, current, next, true);
+                    positions.insert(next, walk.len());
+                    walk.push(next);
+                }
+
+                steps += 1;
+                if steps % frame_interval == 0 {
+                    send_frame(&mut maze, sender)?;
+                }
+            }
+
+            for pair in walk.windows(2) {
+                carve_passage(&mut maze, pair[0], pair[1]);
+                in_maze.insert(pair[0]);
+            }
+            send_frame(&mut maze, sender)?;
+        }
+
+        finish_maze(&mut maze, entrance, &mut rng, sender)?;
+        Ok(maze)
+    }
+}
@@ -0,0 +1,184 @@
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{algorithms::Point, CellType, Maze, MazeType};
+
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+impl Maze {
+    fn offset(&self, point: Point, dx: i32, dy: i32) -> Option<Point> {
+        let x = point.x as i32 + dx;
+        let y = point.y as i32 + dy;
+        self.is_valid_coord(x, y).then_some(Point {
+            x: x as usize,
+            y: y as usize,
+        })
+    }
+
+    fn is_open(&self, point: Point) -> bool {
+        self.get_cell(point).get_type() != CellType::Wall
+    }
+
+    /// Cells reachable from `point` in one move.
+    fn open_neighbors(&self, point: Point) -> Vec<Point> {
+        match self.maze_type {
+            MazeType::Thick => DIRECTIONS
+                .iter()
+                .filter_map(|&(dx, dy)| self.offset(point, dx, dy))
+                .filter(|&next| self.is_open(next))
+                .collect(),
+            MazeType::Slim
+            | MazeType::Hex
+            | MazeType::Polar
+            | MazeType::Layered(_)
+            | MazeType::Toroidal(_)
+            | MazeType::Weave => self
+                .neighbors(point)
+                .into_iter()
+                .filter(|&next| self.is_passable(point, next))
+                .collect(),
+        }
+    }
+
+    /// Cells that could be joined to `point` by knocking down a single wall, paired with
+    /// the wall cell in thick mazes.
+    fn closed_neighbors(&self, point: Point) -> Vec<(Point, Option<Point>)> {
+        match self.maze_type {
+            MazeType::Thick => DIRECTIONS
+                .iter()
+                .filter_map(|&(dx, dy)| {
+                    let wall = self.offset(point, dx, dy)?;
+                    let next = self.offset(point, dx * 2, dy * 2)?;
+                    // Keep the outer wall intact
+                    let inner = wall.x > 0 && wall.y > 0;
+                    (inner && !self.is_open(wall) && self.is_open(next))
+                        .then_some((next, Some(wall)))
+                })
+                .collect(),
+            MazeType::Slim
+            | MazeType::Hex
+            | MazeType::Polar
+            | MazeType::Layered(_)
+            | MazeType::Toroidal(_)
+            | MazeType::Weave => self
+                .neighbors(point)
+                .into_iter()
+                .filter(|&next| !self.is_passable(point, next))
+                // Opening a crossing of a weave maze would join its two corridors
+                .filter(|&next| !self.get_cell(next).has_under_passage())
+                .map(|next| (next, None))
+                .collect(),
+        }
+    }
+
+    /// Open cells with a single way out.
+    pub fn dead_ends(&self) -> Vec<Point> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Point { x, y }))
+            .filter(|&point| {
+                self.is_valid_coord(point.x as i32, point.y as i32)
+                    && match self.maze_type {
+                        // An exit placed in the outer wall cannot be braided away
+                        MazeType::Thick => point.x > 0 && point.y > 0 && self.is_open(point),
+                        MazeType::Slim
+                        | MazeType::Hex
+                        | MazeType::Polar
+                        | MazeType::Layered(_)
+                        | MazeType::Toroidal(_)
+                        | MazeType::Weave => true,
+                    }
+                    && self.open_neighbors(point).len() == 1
+            })
+            .collect()
+    }
+
+    /// Removes roughly `fraction` of the dead ends by opening a wall next to them, which
+    /// turns a perfect maze into one with several routes between two cells. Dead ends are
+    /// preferably joined to each other. Returns how many dead ends were removed, call
+    /// `backup` afterwards to keep the change when the maze is reset.
+    pub fn braid<R: Rng + ?Sized>(&mut self, rng: &mut R, fraction: f64) -> usize {
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+        let count = (dead_ends.len() as f64 * fraction.clamp(0.0, 1.0)).round() as usize;
+
+        let mut removed = 0;
+        for point in dead_ends.into_iter().take(count) {
+            // An earlier pass may already have opened this one
+            if self.open_neighbors(point).len() != 1 {
+                continue;
+            }
+
+            let candidates = self.closed_neighbors(point);
+            let joining: Vec<_> = candidates
+                .iter()
+                .filter(|(next, _)| self.open_neighbors(*next).len() == 1)
+                .copied()
+                .collect();
+            let choice = if joining.is_empty() {
+                candidates.choose(rng)
+            } else {
+                joining.choose(rng)
+            };
+
+            match choice {
+                Some(&(_, Some(wall))) => self.mark_cell_as_path(wall),
+                Some(&(next, None)) => self.remove_walls_between_cells(point, next),
+                None => continue,
+            }
+            removed += 1;
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::algorithms::{Algorithm, Budget};
+
+    #[test]
+    fn test_braid_removes_dead_ends() {
+        for maze_type in MazeType::cell_types() {
+            let mut maze = Algorithm::Backtracking
+                .get_maze_generator()
+                .unwrap()
+                .generate(maze_type, 21, 21, Point { x: 1, y: 1 }, 1, None)
+                .unwrap();
+            let before = maze.dead_ends().len();
+
+            let mut rng = StdRng::seed_from_u64(1);
+            assert!(maze.braid(&mut rng, 0.5) > 0);
+            assert!(maze.dead_ends().len() < before);
+
+            maze.braid(&mut rng, 1.0);
+            assert!(maze.dead_ends().is_empty(), "{}", maze_type);
+        }
+    }
+
+    #[test]
+    fn test_pathfinders_finish_on_braided_mazes() {
+        for maze_type in MazeType::cell_types() {
+            let mut maze = Algorithm::AldousBroder
+                .get_maze_generator()
+                .unwrap()
+                .generate(maze_type, 11, 15, Point { x: 1, y: 1 }, 3, None)
+                .unwrap();
+            maze.braid(&mut StdRng::seed_from_u64(3), 1.0);
+
+            for algorithm in Algorithm::pathfinding_algorithms() {
+                let (sender, _receiver) = channel();
+                let budget = Budget::new().with_max_steps(5_000);
+                let outcome = algorithm
+                    .get_pathfinder()
+                    .unwrap()
+                    .find_path_within(&mut maze.clone(), &sender, &budget)
+                    .unwrap();
+                assert!(outcome.stopped.is_none(), "{} {}", algorithm, maze_type);
+                assert!(outcome.reachable, "{} {}", algorithm, maze_type);
+            }
+        }
+    }
+}
@@ -1,6 +1,13 @@
 pub mod algorithms;
+mod braid;
+mod mask;
 mod maze;
 mod maze_cell;
+mod maze_file;
+mod maze_text;
 
+pub use mask::*;
 pub use maze::*;
 pub use maze_cell::*;
+pub use maze_file::*;
+pub use maze_text::*;
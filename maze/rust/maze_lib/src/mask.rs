@@ -0,0 +1,208 @@
+use std::{collections::VecDeque, fmt, fs, io, path::Path, str::FromStr};
+
+use image::{DynamicImage, ImageError};
+
+use crate::algorithms::Point;
+
+/// Cells a shaped maze is made of, one bit per cell of the maze rather than per grid
+/// position. See `Maze::from_mask`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+#[derive(Debug)]
+pub enum MaskError {
+    Io(io::Error),
+    Image(ImageError),
+    /// Not a single cell is set.
+    Empty,
+}
+
+impl fmt::Display for MaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskError::Io(error) => write!(f, "Cannot read mask file: {}", error),
+            MaskError::Image(error) => write!(f, "Cannot read mask image: {}", error),
+            MaskError::Empty => write!(f, "Mask does not contain any cells"),
+        }
+    }
+}
+
+impl std::error::Error for MaskError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MaskError::Io(error) => Some(error),
+            MaskError::Image(error) => Some(error),
+            MaskError::Empty => None,
+        }
+    }
+}
+
+impl From<io::Error> for MaskError {
+    fn from(error: io::Error) -> Self {
+        MaskError::Io(error)
+    }
+}
+
+impl From<ImageError> for MaskError {
+    fn from(error: ImageError) -> Self {
+        MaskError::Image(error)
+    }
+}
+
+impl Mask {
+    /// Mask of the given size, `is_set` tells which cells belong to the maze. Only the
+    /// largest connected region is kept, generators could never reach the others.
+    pub fn new(
+        width: usize,
+        height: usize,
+        is_set: impl Fn(usize, usize) -> bool,
+    ) -> Result<Self, MaskError> {
+        let cells = (0..width * height)
+            .map(|index| is_set(index % width, index / width))
+            .collect();
+        let mask = Mask {
+            width,
+            height,
+            cells,
+        }
+        .largest_region();
+
+        if mask.count() == 0 {
+            return Err(MaskError::Empty);
+        }
+        Ok(mask)
+    }
+
+    /// One cell per pixel, dark opaque pixels are cells and everything else is left out.
+    pub fn from_image(image: &DynamicImage) -> Result<Self, MaskError> {
+        let image = image.to_luma_alpha8();
+        Mask::new(image.width() as usize, image.height() as usize, |x, y| {
+            let [luma, alpha] = image.get_pixel(x as u32, y as u32).0;
+            luma < 128 && alpha >= 128
+        })
+    }
+
+    /// Reads a mask file, PNG images (see `from_image`) by their extension and text masks
+    /// otherwise.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, MaskError> {
+        let path = path.as_ref();
+        let is_png = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if is_png {
+            Mask::from_image(&image::open(path)?)
+        } else {
+            fs::read_to_string(path)?.parse()
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x]
+    }
+
+    /// Number of cells in the mask.
+    pub fn count(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    /// Drops every cell that is not joined to the largest region by a side.
+    fn largest_region(mut self) -> Self {
+        let mut region = vec![None; self.cells.len()];
+        let mut sizes = Vec::new();
+        for start in 0..self.cells.len() {
+            if !self.cells[start] || region[start].is_some() {
+                continue;
+            }
+
+            let id = sizes.len();
+            let mut size = 0;
+            region[start] = Some(id);
+            let mut queue = VecDeque::from([start]);
+            while let Some(index) = queue.pop_front() {
+                size += 1;
+                let point = Point {
+                    x: index % self.width,
+                    y: index / self.width,
+                };
+                let neighbors = [
+                    (point.x + 1, point.y),
+                    (point.x, point.y + 1),
+                    (point.x.wrapping_sub(1), point.y),
+                    (point.x, point.y.wrapping_sub(1)),
+                ];
+                for (x, y) in neighbors {
+                    let next = y.wrapping_mul(self.width).wrapping_add(x);
+                    if self.is_set(x, y) && region[next].is_none() {
+                        region[next] = Some(id);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+
+        // The first of several regions of the same size wins
+        let largest = (0..sizes.len()).rev().max_by_key(|&id| sizes[id]);
+        for (cell, region) in self.cells.iter_mut().zip(region) {
+            *cell = region.is_some() && region == largest;
+        }
+        self
+    }
+}
+
+/// Text masks have a line per row of cells. Dots and spaces are left out, every other
+/// character is a cell. Shorter lines are padded with spaces.
+impl FromStr for Mask {
+    type Err = MaskError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<Vec<char>> = s.lines().map(|line| line.chars().collect()).collect();
+        let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+        Mask::new(width, lines.len(), |x, y| {
+            lines[y].get(x).is_some_and(|&c| c != '.' && c != ' ')
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_text_mask() {
+        let mask: Mask = "###.\n#\n#..#".parse().unwrap();
+        assert_eq!((mask.width(), mask.height()), (4, 3));
+        // The lone cell in the corner is cut off from the rest
+        assert_eq!(mask.count(), 5);
+        assert!(mask.is_set(0, 2));
+        assert!(!mask.is_set(3, 2));
+        assert!(!mask.is_set(1, 1));
+
+        assert!(matches!("..\n  ".parse::<Mask>(), Err(MaskError::Empty)));
+    }
+
+    #[test]
+    fn test_mask_from_image() {
+        let image = GrayImage::from_fn(3, 2, |x, _| Luma([if x == 1 { 255 } else { 0 }]));
+        let mask = Mask::from_image(&DynamicImage::ImageLuma8(image)).unwrap();
+        // Two columns of the same size, the first one is kept
+        assert_eq!(mask.count(), 2);
+        assert!(mask.is_set(0, 1));
+        assert!(!mask.is_set(2, 1));
+    }
+}
@@ -1,37 +1,99 @@
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 use std::fmt;
 
+use crate::algorithms::HexDirection;
 use crate::algorithms::Movements;
 use crate::algorithms::Point;
+use crate::algorithms::PolarDirection;
+use crate::algorithms::StepEvent;
 use crate::CellType;
+use crate::Mask;
 use crate::MazeCell;
 use crate::SlimWallsCellType;
+use crate::Terrain;
 
 #[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
 pub enum MazeType {
     #[default]
     Thick,
     Slim,
+    /// Hexagonal cells with six walls each, see `HexDirection`
+    Hex,
+    /// Concentric rings around a single centre cell, see `PolarDirection`. The height is
+    /// the number of rings and the width the number of cells in the outermost ring.
+    Polar,
+    /// Several slim walls levels joined by stairs, the value is the number of levels.
+    /// Levels are stacked in the grid, level `n` takes rows `n * level_height` up to
+    /// `(n + 1) * level_height`, see `Maze::level`.
+    Layered(usize),
+    /// Slim walls maze whose opposite edges are joined, every cell is used.
+    Toroidal(Wrap),
+    /// Slim walls maze where passages may tunnel beneath a crossing corridor, see
+    /// `MazeCell::has_under_passage`.
+    Weave,
+}
+
+/// Edges of a toroidal maze that are joined to the opposite edge.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Wrap {
+    /// The left edge leads to the right one
+    Horizontal,
+    /// The top edge leads to the bottom one
+    Vertical,
+    Both,
+}
+
+impl Wrap {
+    pub fn horizontal(&self) -> bool {
+        matches!(self, Wrap::Horizontal | Wrap::Both)
+    }
+
+    pub fn vertical(&self) -> bool {
+        matches!(self, Wrap::Vertical | Wrap::Both)
+    }
 }
 
 impl MazeType {
     pub fn cell_types() -> Vec<MazeType> {
-        vec![MazeType::Thick, MazeType::Slim]
+        vec![
+            MazeType::Thick,
+            MazeType::Slim,
+            MazeType::Hex,
+            MazeType::Polar,
+            MazeType::Layered(3),
+            MazeType::Toroidal(Wrap::Horizontal),
+            MazeType::Toroidal(Wrap::Vertical),
+            MazeType::Toroidal(Wrap::Both),
+            MazeType::Weave,
+        ]
     }
 }
 
 impl std::fmt::Display for MazeType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                MazeType::Thick => "Thick",
-                MazeType::Slim => "Slim",
-            }
-        )
+        match self {
+            MazeType::Thick => write!(f, "Thick"),
+            MazeType::Slim => write!(f, "Slim"),
+            MazeType::Hex => write!(f, "Hex"),
+            MazeType::Polar => write!(f, "Polar"),
+            MazeType::Layered(levels) => write!(f, "Layered ({} levels)", levels),
+            MazeType::Toroidal(Wrap::Horizontal) => write!(f, "Toroidal (horizontal wrap)"),
+            MazeType::Toroidal(Wrap::Vertical) => write!(f, "Toroidal (vertical wrap)"),
+            MazeType::Toroidal(Wrap::Both) => write!(f, "Toroidal"),
+            MazeType::Weave => write!(f, "Weave"),
+        }
+    }
+}
+
+/// Cells in ring `ring` of a polar maze. A ring is split further whenever its cells
+/// would get about one and a half times as wide as they are high.
+fn polar_ring_width(ring: usize) -> usize {
+    let mut width = 1;
+    for ring in 1..=ring {
+        let cell_width = 2.0 * std::f64::consts::PI * ring as f64 / width as f64;
+        width *= (cell_width.round() as usize).max(1);
     }
+    width
 }
 
 #[derive(Clone, Default)]
@@ -41,9 +103,17 @@ pub struct Maze {
     pub height: usize,
     cells: Vec<MazeCell>,
     original_cells: Vec<MazeCell>,
+    /// Grid positions that belong to a shaped maze, see `from_mask`
+    shape: Option<Vec<bool>>,
+    /// Indices of the cells changed since the last `take_changes`, each listed once
+    changes: Vec<usize>,
+    /// Marks the cells listed in `changes`, sized to the grid on the first change
+    changed: Vec<bool>,
 }
 
 impl Maze {
+    /// Polar mazes ignore `width`, it follows from the number of rings. Layered mazes get
+    /// `height` rows on every level.
     pub fn new(
         width: usize,
         height: usize,
@@ -52,7 +122,31 @@ impl Maze {
     ) -> Self {
         let default_cell_type = default_cell_type.unwrap_or(CellType::Path);
 
-        let cells = vec![MazeCell::new(default_cell_type); width * height];
+        let mut width = width;
+        let mut height = height;
+        let mut cell = MazeCell::new(default_cell_type);
+        match maze_type {
+            MazeType::Thick | MazeType::Slim | MazeType::Toroidal(_) | MazeType::Weave => {}
+            MazeType::Hex => {
+                for direction in HexDirection::ALL {
+                    cell.set_hex_wall(direction, true);
+                }
+            }
+            MazeType::Polar => {
+                width = polar_ring_width(height.saturating_sub(1));
+                cell.walls = 0;
+                for direction in PolarDirection::WALLS {
+                    cell.set_polar_wall(direction, true);
+                }
+            }
+            MazeType::Layered(levels) => {
+                height *= levels.max(1);
+                cell.set_up_wall(true);
+                cell.set_down_wall(true);
+            }
+        }
+
+        let cells = vec![cell; width * height];
         let original_cells = cells.clone();
 
         Maze {
@@ -61,9 +155,57 @@ impl Maze {
             height,
             cells,
             original_cells,
+            shape: None,
+            changes: Vec::new(),
+            changed: Vec::new(),
         }
     }
 
+    /// Maze in the shape of `mask`, all cells outside of it are left out. Thick mazes
+    /// keep the walls around every cell of the mask. Only square grids can be shaped,
+    /// `None` for the other maze types.
+    pub fn from_mask(
+        mask: &Mask,
+        maze_type: MazeType,
+        default_cell_type: Option<CellType>,
+    ) -> Option<Self> {
+        let (width, height, shape): (usize, usize, Vec<bool>) = match maze_type {
+            MazeType::Thick => {
+                let (width, height) = (mask.width() * 2 + 1, mask.height() * 2 + 1);
+                // A mask cell brings along the walls and corners around it
+                let is_set = |x: usize, y: usize| {
+                    let columns = x.saturating_sub(1) / 2..=x / 2;
+                    (y.saturating_sub(1) / 2..=y / 2)
+                        .any(|row| columns.clone().any(|column| mask.is_set(column, row)))
+                };
+                let shape = (0..width * height)
+                    .map(|index| is_set(index % width, index / width))
+                    .collect();
+                (width, height, shape)
+            }
+            MazeType::Slim | MazeType::Weave => {
+                // The last row and column stay unused, like in any other slim maze
+                let (width, height) = (mask.width() + 1, mask.height() + 1);
+                let shape = (0..width * height)
+                    .map(|index| mask.is_set(index % width, index / width))
+                    .collect();
+                (width, height, shape)
+            }
+            MazeType::Hex | MazeType::Polar | MazeType::Layered(_) | MazeType::Toroidal(_) => {
+                return None
+            }
+        };
+
+        let mut maze = Maze::new(width, height, maze_type, default_cell_type);
+        maze.shape = Some(shape);
+        Some(maze)
+    }
+
+    /// Whether the maze was shaped by a mask.
+    pub fn is_masked(&self) -> bool {
+        self.shape.is_some()
+    }
+
     pub fn from_original(&self) -> Self {
         Maze {
             maze_type: self.maze_type,
@@ -71,15 +213,71 @@ impl Maze {
             height: self.height,
             cells: self.original_cells.clone(),
             original_cells: self.original_cells.clone(),
+            shape: self.shape.clone(),
+            changes: Vec::new(),
+            changed: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
         self.cells = self.original_cells.clone();
+        self.clear_changes();
     }
 
+    /// Makes the current cells the ones to reset to. Changes made so far are no longer
+    /// reported by `take_changes`.
     pub fn backup(&mut self) {
         self.original_cells = self.cells.clone();
+        self.clear_changes();
+    }
+
+    /// Every cell changed since the last call, `reset` or `backup` as a
+    /// `StepEvent::CellChanged`, in index order.
+    pub fn take_changes(&mut self) -> Vec<StepEvent> {
+        let mut changes = std::mem::take(&mut self.changes);
+        changes.sort_unstable();
+        for &index in &changes {
+            self.changed[index] = false;
+        }
+        changes
+            .into_iter()
+            .map(|index| StepEvent::CellChanged {
+                point: Point {
+                    x: index % self.width,
+                    y: index / self.width,
+                },
+                cell: self.cells[index],
+            })
+            .collect()
+    }
+
+    /// Lists the cell for the next `take_changes`, unless it is listed already.
+    fn record_change(&mut self, index: usize) {
+        if self.changed.len() != self.cells.len() {
+            self.changed = vec![false; self.cells.len()];
+        }
+        if !self.changed[index] {
+            self.changed[index] = true;
+            self.changes.push(index);
+        }
+    }
+
+    fn clear_changes(&mut self) {
+        for index in self.changes.drain(..) {
+            self.changed[index] = false;
+        }
+    }
+
+    /// Brings this copy of a maze up to date with an event of an algorithm working on
+    /// another copy. Events other than cell changes, and changes of cells outside of this
+    /// maze, leave the maze as it is.
+    pub fn apply(&mut self, event: &StepEvent) {
+        if let StepEvent::CellChanged { point, cell } = event {
+            if point.x < self.width && point.y < self.height {
+                let index = self.get_index(point.x, point.y);
+                self.cells[index] = *cell;
+            }
+        }
     }
 
     pub fn get_entrance(&self) -> Option<Point> {
@@ -127,15 +325,46 @@ impl Maze {
     pub fn set_cell(&mut self, x: usize, y: usize, value: MazeCell) {
         let index = self.get_index(x, y);
         self.cells[index] = value;
+        self.record_change(index);
+    }
+
+    pub fn set_terrain(&mut self, point: Point, terrain: Terrain) {
+        let index = self.get_index(point.x, point.y);
+        if let Some(cell) = self.cells.get_mut(index) {
+            cell.set_terrain(terrain);
+            self.record_change(index);
+        }
+    }
+
+    /// Cost of stepping onto the given cell.
+    pub fn get_cost(&self, point: Point) -> u32 {
+        self.get_cell(point).cost()
+    }
+
+    /// Turns roughly `density` of the path cells into swamps of random depth.
+    /// Call `backup` afterwards to keep the terrain when the maze is reset.
+    pub fn scatter_terrain<R: Rng>(&mut self, rng: &mut R, density: f64) {
+        let swamps = &Terrain::ALL[1..];
+        for index in 0..self.cells.len() {
+            if self.cells[index].get_type() == CellType::Path && rng.gen_bool(density) {
+                self.cells[index].set_terrain(swamps[rng.gen_range(0..swamps.len())]);
+                self.record_change(index);
+            }
+        }
     }
 
     fn mark_cell(&mut self, point: Point, cell_type: CellType) {
         let index = self.get_index(point.x, point.y);
         if let Some(cell) = self.cells.get_mut(index) {
             cell.mark_cell_as(cell_type);
+            self.record_change(index);
         }
     }
 
+    pub fn mark_cell_as_wall(&mut self, point: Point) {
+        self.mark_cell(point, CellType::Wall);
+    }
+
     pub fn mark_cell_as_visited(&mut self, point: Point) {
         self.mark_cell(point, CellType::Visited);
     }
@@ -157,14 +386,281 @@ impl Maze {
     }
 
     pub fn is_valid_coord(&self, x: i32, y: i32) -> bool {
+        if let Some(shape) = &self.shape {
+            return x >= 0
+                && y >= 0
+                && x < self.width as i32
+                && y < self.height as i32
+                && shape[self.get_index(x as usize, y as usize)];
+        }
+        if self.maze_type == MazeType::Polar {
+            return x >= 0
+                && y >= 0
+                && y < self.height as i32
+                && x < self.ring_width(y as usize) as i32;
+        }
+        if let MazeType::Toroidal(_) = self.maze_type {
+            return x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32;
+        }
+        if let MazeType::Layered(_) = self.maze_type {
+            // Every level keeps its own unused last row, like a slim maze
+            let level_height = self.level_height() as i32;
+            return x >= 0
+                && y >= 0
+                && x < (self.width - 1) as i32
+                && y < self.height as i32
+                && y % level_height < level_height - 1;
+        }
         x >= 0 && y >= 0 && x < (self.width - 1) as i32 && y < (self.height - 1) as i32
     }
 
+    /// Number of cells in row `ring`. Only polar mazes have rows of different lengths.
+    pub fn ring_width(&self, ring: usize) -> usize {
+        match self.maze_type {
+            MazeType::Polar => polar_ring_width(ring),
+            _ => self.width,
+        }
+    }
+
+    /// Number of levels, 1 for everything but layered mazes.
+    pub fn levels(&self) -> usize {
+        match self.maze_type {
+            MazeType::Layered(levels) => levels.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Rows of every level, including the unused last one.
+    pub fn level_height(&self) -> usize {
+        self.height / self.levels()
+    }
+
+    /// Level the given cell is on, level 0 is the ground floor.
+    pub fn level_of(&self, point: Point) -> usize {
+        point.y / self.level_height()
+    }
+
+    /// A single level of a layered maze as a slim walls maze, for drawing. The stairs stay
+    /// in the up and down wall bits of the cells.
+    pub fn level(&self, level: usize) -> Maze {
+        let level_height = self.level_height();
+        let rows = level * level_height * self.width..(level + 1) * level_height * self.width;
+
+        Maze {
+            maze_type: MazeType::Slim,
+            width: self.width,
+            height: level_height,
+            cells: self.cells[rows.clone()].to_vec(),
+            original_cells: self.original_cells[rows].to_vec(),
+            shape: None,
+            changes: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    /// Brings a coordinate one step off a joined edge of a toroidal maze back to the
+    /// opposite edge.
+    fn wrap_coord(&self, x: i32, y: i32) -> (i32, i32) {
+        match self.maze_type {
+            MazeType::Toroidal(wrap) => (
+                if wrap.horizontal() {
+                    x.rem_euclid(self.width as i32)
+                } else {
+                    x
+                },
+                if wrap.vertical() {
+                    y.rem_euclid(self.height as i32)
+                } else {
+                    y
+                },
+            ),
+            _ => (x, y),
+        }
+    }
+
+    /// Step from `current` to the neighbouring `next` on a square grid, stepping over a
+    /// joined edge counts as a single step as well.
+    fn square_direction(&self, current: Point, next: Point) -> (i32, i32) {
+        let (dx, dy) = Movements::calculate_direction(current, next);
+        let unwrap = |delta: i32| match delta {
+            delta if delta > 1 => -1,
+            delta if delta < -1 => 1,
+            delta => delta,
+        };
+        (unwrap(dx), unwrap(dy))
+    }
+
+    /// Crossing between two cells of a weave maze that lie two apart in a straight line,
+    /// if an under-passage joins them.
+    fn under_passage_between(&self, current: Point, next: Point) -> Option<Point> {
+        let (dx, dy) = Movements::calculate_direction(current, next);
+        if !matches!((dx.abs(), dy.abs()), (2, 0) | (0, 2)) {
+            return None;
+        }
+
+        let middle = Point {
+            x: (current.x + next.x) / 2,
+            y: (current.y + next.y) / 2,
+        };
+        self.get_cell(middle)
+            .has_under_passage_along((dx / 2, dy / 2))
+            .then_some(middle)
+    }
+
+    /// Cells in the next ring out that border `point` in a polar maze.
+    fn polar_outward(&self, point: Point) -> Vec<Point> {
+        if point.y + 1 >= self.height {
+            return Vec::new();
+        }
+
+        let ratio = self.ring_width(point.y + 1) / self.ring_width(point.y);
+        (point.x * ratio..(point.x + 1) * ratio)
+            .map(|x| Point { x, y: point.y + 1 })
+            .collect()
+    }
+
+    /// Side of `current` that `neighbor` lies on in a polar maze.
+    fn polar_direction(&self, current: Point, neighbor: Point) -> PolarDirection {
+        if neighbor.y + 1 == current.y {
+            PolarDirection::Inward
+        } else if neighbor.y == current.y + 1 {
+            PolarDirection::Outward
+        } else if neighbor.x == (current.x + 1) % self.ring_width(current.y) {
+            PolarDirection::Clockwise
+        } else {
+            PolarDirection::CounterClockwise
+        }
+    }
+
+    /// Cells one move away from `point`, whether there is a wall in between or not.
+    pub fn neighbors(&self, point: Point) -> Vec<Point> {
+        let offsets: Vec<(i32, i32)> = match self.maze_type {
+            MazeType::Thick | MazeType::Slim | MazeType::Toroidal(_) => {
+                Movements::directions().to_vec()
+            }
+            MazeType::Hex => HexDirection::ALL
+                .iter()
+                .map(|direction| direction.offset(point.y))
+                .collect(),
+            MazeType::Polar => {
+                let width = self.ring_width(point.y);
+                let mut neighbors = Vec::new();
+                if point.y > 0 {
+                    let ratio = width / self.ring_width(point.y - 1);
+                    neighbors.push(Point {
+                        x: point.x / ratio,
+                        y: point.y - 1,
+                    });
+                }
+                // The centre cell has no neighbours in its own ring
+                if width > 1 {
+                    neighbors.push(Point {
+                        x: (point.x + 1) % width,
+                        y: point.y,
+                    });
+                    neighbors.push(Point {
+                        x: (point.x + width - 1) % width,
+                        y: point.y,
+                    });
+                }
+                neighbors.extend(self.polar_outward(point));
+                return neighbors;
+            }
+            MazeType::Layered(_) => {
+                let level_height = self.level_height() as i32;
+                let mut offsets = Movements::directions().to_vec();
+                offsets.extend([(0, level_height), (0, -level_height)]);
+                offsets
+            }
+            MazeType::Weave => {
+                // The far end of every under-passage next to the cell is a neighbour too
+                let mut offsets = Movements::directions().to_vec();
+                for (dx, dy) in Movements::directions() {
+                    let (x, y) = (point.x as i32 + dx, point.y as i32 + dy);
+                    if self.is_valid_coord(x, y)
+                        && self
+                            .get_cell(Point {
+                                x: x as usize,
+                                y: y as usize,
+                            })
+                            .has_under_passage_along((dx, dy))
+                    {
+                        offsets.push((dx * 2, dy * 2));
+                    }
+                }
+                offsets
+            }
+        };
+
+        // A toroidal maze a single cell across wraps around onto the cell itself
+        offsets
+            .into_iter()
+            .filter_map(|(dx, dy)| {
+                let (x, y) = self.wrap_coord(point.x as i32 + dx, point.y as i32 + dy);
+                self.is_valid_coord(x, y).then_some(Point {
+                    x: x as usize,
+                    y: y as usize,
+                })
+            })
+            .filter(|&neighbor| neighbor != point)
+            .collect()
+    }
+
+    /// Fewest moves between two cells if there were no walls.
+    pub fn distance(&self, from: Point, to: Point) -> usize {
+        match self.maze_type {
+            MazeType::Thick | MazeType::Slim => from.x.abs_diff(to.x) + from.y.abs_diff(to.y),
+            MazeType::Hex => {
+                // Cube coordinates of the offset rows
+                let cube = |point: Point| {
+                    let q = point.x as i64 - (point.y as i64 - (point.y as i64 & 1)) / 2;
+                    (q, point.y as i64)
+                };
+                let ((q1, r1), (q2, r2)) = (cube(from), cube(to));
+                let (dq, dr) = (q1 - q2, r1 - r2);
+                ((dq.abs() + dr.abs() + (dq + dr).abs()) / 2) as usize
+            }
+            // Moving around a ring can be as short as a single step near the centre
+            MazeType::Polar => from.y.abs_diff(to.y),
+            // A move through an under-passage covers two cells
+            MazeType::Weave => (from.x.abs_diff(to.x) + from.y.abs_diff(to.y)).div_ceil(2),
+            MazeType::Toroidal(wrap) => {
+                // Going the other way round over a joined edge may be shorter
+                let wrapped = |delta: usize, size: usize, wraps: bool| {
+                    if wraps {
+                        delta.min(size - delta)
+                    } else {
+                        delta
+                    }
+                };
+                wrapped(from.x.abs_diff(to.x), self.width, wrap.horizontal())
+                    + wrapped(from.y.abs_diff(to.y), self.height, wrap.vertical())
+            }
+            MazeType::Layered(_) => {
+                let level_height = self.level_height();
+                from.x.abs_diff(to.x)
+                    + (from.y % level_height).abs_diff(to.y % level_height)
+                    + self.level_of(from).abs_diff(self.level_of(to))
+            }
+        }
+    }
+
     pub fn is_not_passable(&self, current: Point, next: Point) -> bool {
         match self.maze_type {
             MazeType::Thick => self.get_cell(next).get_type() == CellType::Wall,
-            MazeType::Slim => {
-                let direction = Movements::calculate_direction(current, next);
+            MazeType::Layered(_) if self.level_of(current) != self.level_of(next) => {
+                let (lower, upper) = if next.y > current.y {
+                    (current, next)
+                } else {
+                    (next, current)
+                };
+                self.get_cell(lower).has_up_wall() && self.get_cell(upper).has_down_wall()
+            }
+            MazeType::Weave if current.x.abs_diff(next.x) + current.y.abs_diff(next.y) == 2 => {
+                self.under_passage_between(current, next).is_none()
+            }
+            MazeType::Slim | MazeType::Layered(_) | MazeType::Toroidal(_) | MazeType::Weave => {
+                let direction = self.square_direction(current, next);
                 let opposite_direction =
                     Movements::get_opposite_direction(direction.0, direction.1);
 
@@ -173,6 +669,24 @@ impl Maze {
                         .get_cell(next)
                         .has_wall_in_direction(opposite_direction)
             }
+            MazeType::Hex => {
+                let direction = HexDirection::between(current, next);
+
+                self.get_cell(current).has_hex_wall(direction)
+                    && self.get_cell(next).has_hex_wall(direction.opposite())
+            }
+            MazeType::Polar => match self.polar_direction(current, next) {
+                PolarDirection::Inward => self
+                    .get_cell(current)
+                    .has_polar_wall(PolarDirection::Inward),
+                PolarDirection::Outward => {
+                    self.get_cell(next).has_polar_wall(PolarDirection::Inward)
+                }
+                direction => {
+                    self.get_cell(current).has_polar_wall(direction)
+                        && self.get_cell(next).has_polar_wall(direction.opposite())
+                }
+            },
         }
     }
 
@@ -181,77 +695,135 @@ impl Maze {
     }
 
     pub fn remove_walls_between_cells(&mut self, current: Point, neighbor: Point) {
+        self.set_walls_between_cells(current, neighbor, false);
+    }
+
+    pub fn add_walls_between_cells(&mut self, current: Point, neighbor: Point) {
+        self.set_walls_between_cells(current, neighbor, true);
+    }
+
+    fn set_walls_between_cells(&mut self, current: Point, neighbor: Point, has_wall: bool) {
         assert_ne!(
             self.maze_type,
             MazeType::Thick,
             "This functions should never be called in thick walls maze!"
         );
 
-        let direction = Movements::calculate_direction(current, neighbor);
-        let opposite_direction = Movements::get_opposite_direction(direction.0, direction.1);
-
         let current_idx = self.get_index(current.x, current.y);
         let neighbor_idx = self.get_index(neighbor.x, neighbor.y);
+        self.record_change(current_idx);
+        self.record_change(neighbor_idx);
+
+        if self.maze_type == MazeType::Hex {
+            let direction = HexDirection::between(current, neighbor);
+            self.cells[current_idx].set_hex_wall(direction, has_wall);
+            self.cells[neighbor_idx].set_hex_wall(direction.opposite(), has_wall);
+            return;
+        }
+        if self.maze_type == MazeType::Polar {
+            // Outward walls have no bit, setting them is a no-op
+            let direction = self.polar_direction(current, neighbor);
+            self.cells[current_idx].set_polar_wall(direction, has_wall);
+            self.cells[neighbor_idx].set_polar_wall(direction.opposite(), has_wall);
+            return;
+        }
+        if self.maze_type == MazeType::Weave
+            && current.x.abs_diff(neighbor.x) + current.y.abs_diff(neighbor.y) == 2
+        {
+            // The walls of both ends stay, the passage runs beneath the cell in between
+            let middle = self.get_index((current.x + neighbor.x) / 2, (current.y + neighbor.y) / 2);
+            self.cells[middle].set_under_passage(!has_wall);
+            self.record_change(middle);
+            return;
+        }
+        if self.level_of(current) != self.level_of(neighbor) {
+            let (lower, upper) = if neighbor.y > current.y {
+                (current_idx, neighbor_idx)
+            } else {
+                (neighbor_idx, current_idx)
+            };
+            self.cells[lower].set_up_wall(has_wall);
+            self.cells[upper].set_down_wall(has_wall);
+            return;
+        }
+
+        let direction = self.square_direction(current, neighbor);
+        let opposite_direction = Movements::get_opposite_direction(direction.0, direction.1);
 
         if let Some(cell) = self.cells.get_mut(current_idx) {
-            cell.set_wall_by_direction(direction, false);
+            cell.set_wall_by_direction(direction, has_wall);
         }
         if let Some(cell) = self.cells.get_mut(neighbor_idx) {
-            cell.set_wall_by_direction(opposite_direction, false);
+            cell.set_wall_by_direction(opposite_direction, has_wall);
         }
     }
 
-    pub fn get_random_boundary_point(&self, rng: &mut ThreadRng) -> Point {
-        let side = rng.gen_range(0..4); // 0: Top, 1: Right, 2: Bottom, 3: Left
-
-        let (x, y) = match side {
-            0 => (rng.gen_range(1..self.width - 1), 0),
-            1 => (self.width - 1, rng.gen_range(1..self.height - 1)),
-            2 => (rng.gen_range(1..self.width - 1), self.height - 1),
-            3 => (0, rng.gen_range(1..self.height - 1)),
-            _ => unreachable!(),
-        };
-
-        // Ensure at least one adjacent point is ThickMazeCellType::Path
-        let adjacent_points = [
-            (x, y.saturating_sub(1)),
-            (x.wrapping_add(1), y),
-            (x, y.wrapping_add(1)),
-            (x.saturating_sub(1), y),
-        ];
-
-        if adjacent_points.iter().any(|&(cx, cy)| {
-            self.is_valid_coord(cx as i32, cy as i32)
-                && self.get_cell(Point { x: cx, y: cy }).get_type() == CellType::Path
-        }) {
-            Point { x, y }
-        } else {
-            self.get_random_boundary_point(rng)
-        }
+    /// Random cell on the outer edge of the maze for the exit, never the entrance. `None`
+    /// if the edge has no room for one, e.g. a shaped maze of a single cell.
+    pub fn get_random_boundary_point<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Point> {
+        self.boundary().choose(rng).copied()
     }
-}
 
-impl fmt::Debug for Maze {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                match self.get_cell(Point { x, y }).get_type() {
-                    CellType::Wall => write!(f, "██")?,
-                    CellType::Path => write!(f, "  ")?,
-                    CellType::Entrance => write!(f, " >")?,
-                    CellType::Exit => write!(f, " E")?,
-                    CellType::Visited => write!(f, " v")?,
-                    CellType::FinalPath => write!(f, " F")?,
-                }
+    /// Positions the exit may take, on the edge of the valid cells. Thick mazes have it
+    /// next to a path, the other types on a cell at the edge. Polar mazes use their outer
+    /// ring, layered mazes the edge of the top level, and the joined edges of a toroidal
+    /// maze are no edge.
+    fn boundary(&self) -> Vec<Point> {
+        let top_level = (self.levels() - 1) * self.level_height();
+        let leads_outside = |point: Point, (dx, dy): (i32, i32)| {
+            !self.is_valid_coord(point.x as i32 + dx, point.y as i32 + dy)
+        };
+        let is_on_edge = |point: Point| match self.maze_type {
+            MazeType::Polar => point.y == self.height - 1,
+            MazeType::Toroidal(Wrap::Horizontal) => point.y == 0 || point.y == self.height - 1,
+            MazeType::Toroidal(Wrap::Vertical) => point.x == 0 || point.x == self.width - 1,
+            MazeType::Toroidal(Wrap::Both) => true,
+            // Only in line with a cell, a wall between two cells of the edge would join them
+            MazeType::Thick => {
+                (point.y % 2 == 1
+                    && (leads_outside(point, (-1, 0)) || leads_outside(point, (1, 0))))
+                    || (point.x % 2 == 1
+                        && (leads_outside(point, (0, -1)) || leads_outside(point, (0, 1))))
             }
-            writeln!(f)?;
-        }
-        Ok(())
+            _ => Movements::directions()
+                .iter()
+                .any(|&direction| leads_outside(point, direction)),
+        };
+        // Corners of a thick maze touch no cell, the exit could not be reached
+        let borders_path = |point: Point| {
+            Movements::directions().iter().any(|&(dx, dy)| {
+                let (x, y) = (point.x as i32 + dx, point.y as i32 + dy);
+                self.is_valid_coord(x, y)
+                    && self
+                        .get_cell(Point {
+                            x: x as usize,
+                            y: y as usize,
+                        })
+                        .get_type()
+                        == CellType::Path
+            })
+        };
+
+        (0..self.width * self.height)
+            .map(|index| Point {
+                x: index % self.width,
+                y: index / self.width,
+            })
+            .filter(|&point| {
+                self.is_valid_coord(point.x as i32, point.y as i32)
+                    && point.y >= top_level
+                    && !self.get_cell(point).is_entrance()
+                    && is_on_edge(point)
+                    && (self.maze_type != MazeType::Thick || borders_path(point))
+            })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
     use super::*;
 
     #[test]
@@ -265,4 +837,161 @@ mod tests {
         assert!(!maze.get_cell(current).has_wall_in_direction((0, 1)));
         assert!(!maze.get_cell(neighbor).has_wall_in_direction((0, -1)));
     }
+
+    #[test]
+    fn test_layered_exit_is_on_the_top_edge() {
+        let maze = Maze::new(6, 6, MazeType::Layered(2), Some(CellType::Path));
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut exits = Vec::new();
+        for _ in 0..200 {
+            let exit = maze.get_random_boundary_point(&mut rng).unwrap();
+            assert!(
+                maze.is_valid_coord(exit.x as i32, exit.y as i32),
+                "{:?}",
+                exit
+            );
+            assert_eq!(maze.level_of(exit), 1);
+            // Columns 0 to 4 and rows 0 to 4 of the level hold its cells
+            let y = exit.y - 6;
+            assert!(exit.x == 0 || exit.x == 4 || y == 0 || y == 4, "{:?}", exit);
+            exits.push((exit.x, y));
+        }
+        // The last column and row of cells are an edge too
+        assert!(exits.iter().any(|&(x, _)| x == 4));
+        assert!(exits.iter().any(|&(_, y)| y == 4));
+    }
+
+    #[test]
+    fn test_hex_neighbors() {
+        let maze = Maze::new(5, 5, MazeType::Hex, Some(CellType::Path));
+
+        // Odd rows are shifted right, so their diagonal neighbours are one column further
+        let mut neighbors = maze.neighbors(Point { x: 1, y: 1 });
+        neighbors.sort_by_key(|point| (point.y, point.x));
+        let expected = [(1, 0), (2, 0), (0, 1), (2, 1), (1, 2), (2, 2)];
+        assert_eq!(neighbors, expected.map(|(x, y)| Point { x, y }).to_vec());
+        assert_eq!(maze.neighbors(Point { x: 0, y: 0 }).len(), 2);
+
+        assert_eq!(maze.distance(Point { x: 0, y: 0 }, Point { x: 0, y: 1 }), 1);
+        assert_eq!(maze.distance(Point { x: 0, y: 0 }, Point { x: 3, y: 3 }), 5);
+        assert_eq!(maze.distance(Point { x: 3, y: 0 }, Point { x: 0, y: 2 }), 4);
+    }
+
+    #[test]
+    fn test_polar_neighbors() {
+        let maze = Maze::new(0, 4, MazeType::Polar, Some(CellType::Path));
+        assert_eq!(
+            (0..4).map(|ring| maze.ring_width(ring)).collect::<Vec<_>>(),
+            vec![1, 6, 12, 24]
+        );
+        assert_eq!(maze.width, 24);
+
+        assert_eq!(maze.neighbors(Point { x: 0, y: 0 }).len(), 6);
+        // Inward, clockwise, counter-clockwise and two cells further out
+        assert_eq!(
+            maze.neighbors(Point { x: 0, y: 2 }),
+            [(0, 1), (1, 2), (11, 2), (0, 3), (1, 3)].map(|(x, y)| Point { x, y })
+        );
+        assert!(!maze.is_valid_coord(12, 2));
+    }
+
+    #[test]
+    fn test_layered_neighbors() {
+        let mut maze = Maze::new(4, 4, MazeType::Layered(3), Some(CellType::Path));
+        assert_eq!((maze.height, maze.level_height()), (12, 4));
+
+        // Same level plus the cells right above and below, the unused row is skipped
+        let point = Point { x: 1, y: 6 };
+        assert_eq!(maze.level_of(point), 1);
+        assert_eq!(
+            maze.neighbors(point),
+            [(2, 6), (1, 5), (0, 6), (1, 10), (1, 2)].map(|(x, y)| Point { x, y })
+        );
+        assert!(!maze.is_valid_coord(1, 7));
+        assert_eq!(maze.distance(point, Point { x: 0, y: 8 }), 4);
+
+        assert!(maze.is_not_passable(point, Point { x: 1, y: 10 }));
+        maze.remove_walls_between_cells(point, Point { x: 1, y: 10 });
+        assert!(maze.is_passable(Point { x: 1, y: 10 }, point));
+        assert!(maze.is_not_passable(point, Point { x: 1, y: 2 }));
+    }
+
+    #[test]
+    fn test_toroidal_neighbors() {
+        let mut maze = Maze::new(5, 4, MazeType::Toroidal(Wrap::Horizontal), None);
+
+        // The left edge leads to the right one, the top edge is closed
+        assert_eq!(
+            maze.neighbors(Point { x: 0, y: 0 }),
+            [(0, 1), (1, 0), (4, 0)].map(|(x, y)| Point { x, y })
+        );
+        assert_eq!(maze.distance(Point { x: 0, y: 0 }, Point { x: 4, y: 3 }), 4);
+
+        maze.remove_walls_between_cells(Point { x: 4, y: 2 }, Point { x: 0, y: 2 });
+        assert!(!maze.get_cell(Point { x: 4, y: 2 }).has_right_wall());
+        assert!(!maze.get_cell(Point { x: 0, y: 2 }).has_left_wall());
+        assert!(maze.is_passable(Point { x: 0, y: 2 }, Point { x: 4, y: 2 }));
+    }
+
+    #[test]
+    fn test_weave_under_passage() {
+        let mut maze = Maze::new(4, 4, MazeType::Weave, Some(CellType::Path));
+        let (start, crossing, end) = (
+            Point { x: 0, y: 1 },
+            Point { x: 1, y: 1 },
+            Point { x: 2, y: 1 },
+        );
+        // A vertical corridor through the crossing
+        maze.remove_walls_between_cells(Point { x: 1, y: 0 }, crossing);
+        maze.remove_walls_between_cells(crossing, Point { x: 1, y: 2 });
+        assert!(!maze.neighbors(start).contains(&end));
+
+        maze.remove_walls_between_cells(start, end);
+        assert!(maze.get_cell(crossing).has_under_passage_along((1, 0)));
+        assert!(maze.neighbors(start).contains(&end));
+        assert!(maze.is_passable(end, start));
+        // The corridors only cross, they do not join
+        assert!(maze.is_not_passable(start, crossing));
+        assert_eq!(maze.distance(start, end), 1);
+    }
+
+    #[test]
+    fn test_terrain_survives_marking() {
+        let mut maze = Maze::new(3, 3, MazeType::Thick, Some(CellType::Path));
+        let point = Point { x: 1, y: 1 };
+
+        maze.set_terrain(point, Terrain::SwampMedium);
+        maze.mark_cell_as_visited(point);
+
+        assert_eq!(maze.get_cell(point).get_terrain(), Terrain::SwampMedium);
+        assert_eq!(maze.get_cost(point), Terrain::SwampMedium.cost());
+    }
+
+    #[test]
+    fn test_changes_are_listed_once() {
+        let mut maze = Maze::new(5, 5, MazeType::Thick, Some(CellType::Path));
+        let mut rng = StdRng::seed_from_u64(2);
+        // Changes pile up without anybody taking them, but every cell only once
+        for _ in 0..20 {
+            maze.scatter_terrain(&mut rng, 1.0);
+            maze.mark_cell_as_visited(Point { x: 1, y: 1 });
+        }
+        assert_eq!(maze.changes.len(), 25);
+        assert_eq!(maze.take_changes().len(), 25);
+        assert!(maze.take_changes().is_empty());
+
+        // A frame of a larger maze leaves the cells outside of this one alone
+        let mut small = Maze::new(3, 3, MazeType::Thick, Some(CellType::Path));
+        small.apply(&StepEvent::CellChanged {
+            point: Point { x: 4, y: 4 },
+            cell: MazeCell::new(CellType::Wall),
+        });
+        assert_eq!(
+            format!("{:?}", small),
+            format!(
+                "{:?}",
+                Maze::new(3, 3, MazeType::Thick, Some(CellType::Path))
+            )
+        );
+    }
 }
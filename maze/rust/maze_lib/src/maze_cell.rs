@@ -1,3 +1,5 @@
+use crate::algorithms::{HexDirection, PolarDirection};
+
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub enum CellType {
     Wall,
@@ -9,9 +11,40 @@ pub enum CellType {
     FinalPath,
 }
 
+/// Ground the cell is made of. Terrain only affects how expensive it is to step onto
+/// the cell, so it survives marking the cell as visited or as a part of the final path.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Terrain {
+    #[default]
+    Normal,
+    SwampLite,
+    SwampMedium,
+    SwampHeavy,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 4] = [
+        Terrain::Normal,
+        Terrain::SwampLite,
+        Terrain::SwampMedium,
+        Terrain::SwampHeavy,
+    ];
+
+    /// Cost of entering a cell with this terrain.
+    pub fn cost(&self) -> u32 {
+        match self {
+            Terrain::Normal => 1,
+            Terrain::SwampLite => 2,
+            Terrain::SwampMedium => 4,
+            Terrain::SwampHeavy => 8,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct MazeCell {
     cell_type: CellType,
+    terrain: Terrain,
     pub walls: u8,
 }
 
@@ -20,14 +53,22 @@ impl Default for MazeCell {
         MazeCell {
             walls: 0b1111,
             cell_type: CellType::default(),
+            terrain: Terrain::default(),
         }
     }
 }
 
 impl MazeCell {
+    /// Layered mazes keep the slim walls in the lower bits.
+    pub const UP_WALL: u8 = 0b01_0000;
+    pub const DOWN_WALL: u8 = 0b10_0000;
+    /// Weave mazes mark crossings, a passage runs beneath the cell's own corridor.
+    pub const UNDER_PASSAGE: u8 = 0b100_0000;
+
     pub fn new(cell_type: CellType) -> Self {
         MazeCell {
             cell_type,
+            terrain: Terrain::default(),
             walls: 0b1111,
         }
     }
@@ -40,6 +81,18 @@ impl MazeCell {
         self.cell_type
     }
 
+    pub fn get_terrain(&self) -> Terrain {
+        self.terrain
+    }
+
+    pub fn set_terrain(&mut self, terrain: Terrain) {
+        self.terrain = terrain;
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.terrain.cost()
+    }
+
     pub fn is_entrance(&self) -> bool {
         self.cell_type == CellType::Entrance
     }
@@ -47,6 +100,79 @@ impl MazeCell {
     pub fn is_exit(&self) -> bool {
         self.cell_type == CellType::Exit
     }
+
+    pub fn has_hex_wall(&self, direction: HexDirection) -> bool {
+        self.walls & direction.wall() != 0
+    }
+
+    pub fn set_hex_wall(&mut self, direction: HexDirection, has_wall: bool) {
+        if has_wall {
+            self.walls |= direction.wall();
+        } else {
+            self.walls &= !direction.wall();
+        }
+    }
+
+    /// Always `false` for `PolarDirection::Outward`, those walls belong to the outer cells.
+    pub fn has_polar_wall(&self, direction: PolarDirection) -> bool {
+        self.walls & direction.wall() != 0
+    }
+
+    pub fn set_polar_wall(&mut self, direction: PolarDirection, has_wall: bool) {
+        if has_wall {
+            self.walls |= direction.wall();
+        } else {
+            self.walls &= !direction.wall();
+        }
+    }
+
+    /// Ceiling of a cell in a layered maze, without it there are stairs to the level above.
+    pub fn has_up_wall(&self) -> bool {
+        self.walls & Self::UP_WALL != 0
+    }
+
+    pub fn set_up_wall(&mut self, has_wall: bool) {
+        if has_wall {
+            self.walls |= Self::UP_WALL;
+        } else {
+            self.walls &= !Self::UP_WALL;
+        }
+    }
+
+    /// Crossing of a weave maze. The cell's own corridor runs straight through it and the
+    /// under-passage goes across, beneath the two walls of the cell.
+    pub fn has_under_passage(&self) -> bool {
+        self.walls & Self::UNDER_PASSAGE != 0
+    }
+
+    pub fn set_under_passage(&mut self, has_passage: bool) {
+        if has_passage {
+            self.walls |= Self::UNDER_PASSAGE;
+        } else {
+            self.walls &= !Self::UNDER_PASSAGE;
+        }
+    }
+
+    /// Whether the under-passage of a crossing runs along `direction`, that is through
+    /// the walls on both of its ends.
+    pub fn has_under_passage_along(&self, direction: (i32, i32)) -> bool {
+        self.has_under_passage()
+            && self.has_wall_in_direction(direction)
+            && self.has_wall_in_direction((-direction.0, -direction.1))
+    }
+
+    /// Floor of a cell in a layered maze, without it there are stairs to the level below.
+    pub fn has_down_wall(&self) -> bool {
+        self.walls & Self::DOWN_WALL != 0
+    }
+
+    pub fn set_down_wall(&mut self, has_wall: bool) {
+        if has_wall {
+            self.walls |= Self::DOWN_WALL;
+        } else {
+            self.walls &= !Self::DOWN_WALL;
+        }
+    }
 }
 
 pub trait SlimWallsCellType {
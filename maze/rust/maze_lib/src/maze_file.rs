@@ -0,0 +1,254 @@
+use std::{fmt, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{algorithms::Point, CellType, Maze, MazeCell, MazeType, Terrain};
+
+/// On-disk layout of the `.maze` files written by the Python implementation.
+#[derive(Serialize, Deserialize)]
+struct MazeFile {
+    width: usize,
+    height: usize,
+    grid: Vec<Vec<Option<String>>>,
+}
+
+#[derive(Debug)]
+pub enum MazeFileError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    InvalidSize { width: usize, height: usize },
+    UnknownCell { x: usize, y: usize, token: String },
+    UnsupportedMazeType(MazeType),
+}
+
+impl fmt::Display for MazeFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeFileError::Io(error) => write!(f, "Cannot access maze file: {}", error),
+            MazeFileError::Json(error) => write!(f, "Malformed maze file: {}", error),
+            MazeFileError::InvalidSize { width, height } => write!(
+                f,
+                "Grid does not match the declared size of {}x{}",
+                width, height
+            ),
+            MazeFileError::UnknownCell { x, y, token } => {
+                write!(f, "Unknown cell {:?} at ({}, {})", token, x, y)
+            }
+            MazeFileError::UnsupportedMazeType(maze_type) => {
+                write!(f, "{} mazes cannot be stored in a .maze file", maze_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MazeFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MazeFileError::Io(error) => Some(error),
+            MazeFileError::Json(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MazeFileError {
+    fn from(error: io::Error) -> Self {
+        MazeFileError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for MazeFileError {
+    fn from(error: serde_json::Error) -> Self {
+        MazeFileError::Json(error)
+    }
+}
+
+const WALL_TOKENS: [&str; 11] = [
+    "WALL_HORIZONTAL",
+    "WALL_VERTICAL",
+    "WALL_CORNER_TL",
+    "WALL_CORNER_TR",
+    "WALL_CORNER_BL",
+    "WALL_CORNER_BR",
+    "WALL_CROSS",
+    "WALL_T_CROSS",
+    "WALL_B_CROSS",
+    "WALL_L_CROSS",
+    "WALL_R_CROSS",
+];
+
+fn cell_from_token(token: Option<&str>) -> Option<MazeCell> {
+    let (cell_type, terrain) = match token {
+        // The Python loader turns missing cells into floor as well
+        None | Some("FLOOR") => (CellType::Path, Terrain::Normal),
+        Some("SWAMP_LITE") => (CellType::Path, Terrain::SwampLite),
+        Some("SWAMP_MEDIUM") => (CellType::Path, Terrain::SwampMedium),
+        Some("SWAMP_HEAVY") => (CellType::Path, Terrain::SwampHeavy),
+        Some("START") => (CellType::Entrance, Terrain::Normal),
+        Some("FINISH") => (CellType::Exit, Terrain::Normal),
+        Some(token) if WALL_TOKENS.contains(&token) => (CellType::Wall, Terrain::Normal),
+        Some(_) => return None,
+    };
+
+    let mut cell = MazeCell::new(cell_type);
+    cell.set_terrain(terrain);
+    Some(cell)
+}
+
+/// Picks the box-drawing wall token that joins up with the neighbouring walls.
+fn wall_token(up: bool, down: bool, left: bool, right: bool) -> &'static str {
+    match (up, down, left, right) {
+        (true, true, true, true) => "WALL_CROSS",
+        (false, true, true, true) => "WALL_T_CROSS",
+        (true, false, true, true) => "WALL_B_CROSS",
+        (true, true, false, true) => "WALL_L_CROSS",
+        (true, true, true, false) => "WALL_R_CROSS",
+        (false, true, false, true) => "WALL_CORNER_TL",
+        (false, true, true, false) => "WALL_CORNER_TR",
+        (true, false, false, true) => "WALL_CORNER_BL",
+        (true, false, true, false) => "WALL_CORNER_BR",
+        (true, _, false, false) | (_, true, false, false) => "WALL_VERTICAL",
+        _ => "WALL_HORIZONTAL",
+    }
+}
+
+impl Maze {
+    /// Loads a maze stored in the `.maze` JSON format of the Python implementation.
+    pub fn load(path: impl AsRef<Path>) -> Result<Maze, MazeFileError> {
+        Maze::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Saves the maze in the `.maze` JSON format of the Python implementation.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MazeFileError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn from_json(json: &str) -> Result<Maze, MazeFileError> {
+        let file: MazeFile = serde_json::from_str(json)?;
+
+        if file.grid.len() != file.height || file.grid.iter().any(|row| row.len() != file.width) {
+            return Err(MazeFileError::InvalidSize {
+                width: file.width,
+                height: file.height,
+            });
+        }
+
+        let mut maze = Maze::new(file.width, file.height, MazeType::Thick, None);
+        for (y, row) in file.grid.iter().enumerate() {
+            for (x, token) in row.iter().enumerate() {
+                let cell = cell_from_token(token.as_deref()).ok_or_else(|| {
+                    MazeFileError::UnknownCell {
+                        x,
+                        y,
+                        token: token.clone().unwrap_or_default(),
+                    }
+                })?;
+                maze.set_cell(x, y, cell);
+            }
+        }
+
+        maze.backup();
+        Ok(maze)
+    }
+
+    /// Serializes the maze layout. Pathfinding marks are dropped, the entrance and exit
+    /// are taken from the original maze.
+    pub fn to_json(&self) -> Result<String, MazeFileError> {
+        if self.maze_type != MazeType::Thick {
+            return Err(MazeFileError::UnsupportedMazeType(self.maze_type));
+        }
+
+        let entrance = self.get_entrance();
+        let exit = self.get_exit();
+        let is_wall = |x: Option<usize>, y: Option<usize>| match (x, y) {
+            (Some(x), Some(y)) if x < self.width && y < self.height => {
+                self.get_cell(Point { x, y }).get_type() == CellType::Wall
+            }
+            _ => false,
+        };
+
+        let grid = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let point = Point { x, y };
+                        let cell = self.get_cell(point);
+                        let token = match cell.get_type() {
+                            CellType::Wall => wall_token(
+                                is_wall(Some(x), y.checked_sub(1)),
+                                is_wall(Some(x), Some(y + 1)),
+                                is_wall(x.checked_sub(1), Some(y)),
+                                is_wall(Some(x + 1), Some(y)),
+                            ),
+                            CellType::Entrance => "START",
+                            CellType::Exit => "FINISH",
+                            _ if Some(point) == entrance => "START",
+                            _ if Some(point) == exit => "FINISH",
+                            _ => match cell.get_terrain() {
+                                Terrain::Normal => "FLOOR",
+                                Terrain::SwampLite => "SWAMP_LITE",
+                                Terrain::SwampMedium => "SWAMP_MEDIUM",
+                                Terrain::SwampHeavy => "SWAMP_HEAVY",
+                            },
+                        };
+                        Some(token.to_string())
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(serde_json::to_string(&MazeFile {
+            width: self.width,
+            height: self.height,
+            grid,
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTHON_MAZE: &str = include_str!("../../../python/test.maze");
+
+    #[test]
+    fn test_load_python_maze() {
+        let maze = Maze::from_json(PYTHON_MAZE).unwrap();
+
+        assert_eq!((maze.width, maze.height), (40, 40));
+        assert_eq!(maze.get_entrance(), Some(Point { x: 1, y: 1 }));
+        assert_eq!(maze.get_exit(), Some(Point { x: 38, y: 38 }));
+        assert_eq!(
+            maze.get_cell(Point { x: 2, y: 1 }).get_terrain(),
+            Terrain::SwampLite
+        );
+        assert_eq!(
+            maze.get_cell(Point { x: 0, y: 0 }).get_type(),
+            CellType::Wall
+        );
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let maze = Maze::from_json(PYTHON_MAZE).unwrap();
+        let reloaded = Maze::from_json(&maze.to_json().unwrap()).unwrap();
+
+        for y in 0..maze.height {
+            for x in 0..maze.width {
+                let point = Point { x, y };
+                assert_eq!(maze.get_cell(point), reloaded.get_cell(point));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_token() {
+        let json = r#"{"width": 2, "height": 1, "grid": [["FLOOR", "LAVA"]]}"#;
+
+        assert!(matches!(
+            Maze::from_json(json),
+            Err(MazeFileError::UnknownCell { x: 1, y: 0, .. })
+        ));
+    }
+}
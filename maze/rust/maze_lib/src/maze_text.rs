@@ -0,0 +1,1165 @@
+//! Plain-text maze format, the same one `{:?}` prints.
+//!
+//! Every cell is two characters wide:
+//!
+//! | Text | Cell                         |
+//! |------|------------------------------|
+//! | `██` | `CellType::Wall`             |
+//! | `  ` | `CellType::Path`             |
+//! | `░░` | path, `Terrain::SwampLite`   |
+//! | `▒▒` | path, `Terrain::SwampMedium` |
+//! | `▓▓` | path, `Terrain::SwampHeavy`  |
+//! | ` >` | `CellType::Entrance`         |
+//! | ` E` | `CellType::Exit`             |
+//! | ` v` | `CellType::Visited`          |
+//! | ` F` | `CellType::FinalPath`        |
+//!
+//! The entrance, the exit and the cells marked by a search put the terrain of their cell
+//! in front of their letter instead of the space, a visited `Terrain::SwampLite` cell is
+//! `░v`.
+//!
+//! Thick mazes are simply one line of cells per maze row:
+//!
+//! ```text
+//! ██████████
+//! ██ >    ██
+//! ██████  ██
+//! ██ E    ██
+//! ██████████
+//! ```
+//!
+//! Slim mazes put box-drawing walls around the cells. Wall lines and cell lines alternate,
+//! a wall is `──` above or below a cell and `│` to the left or right of it. Junction
+//! characters are only decorative and are ignored by the parser.
+//!
+//! ```text
+//! ┌──┬─────┐
+//! │ >│     │
+//! │  ╵  ╶──┘
+//! │       E
+//! └────────╴
+//! ```
+//!
+//! Hexagonal mazes draw every cell as `|  |` between its west and east walls, with the
+//! diagonal walls `/` and `\` on the lines in between. Odd rows are shifted right by half
+//! a cell.
+//!
+//! ```text
+//!  / \ / \ / \
+//! |  >      ░░|
+//!  \   \   \ / \
+//!   |   |      E|
+//!    \ / \ / \ /
+//! ```
+//!
+//! Polar mazes take two lines per ring, from the centre outwards. The first line has `--`
+//! over every cell with an inward wall, the second one the cells with `|` for a wall on
+//! their counter-clockwise side. The outermost ring is always closed.
+//!
+//! ```text
+//!  --
+//! | >
+//!     -- --    -- --
+//! |        |
+//!        --       --    -- --       --
+//! |  |     |  |    E|        |  |
+//! ```
+//!
+//! Layered mazes print every level as a slim maze, from the ground floor up, separated by
+//! an empty line. Stairs replace the first character of a cell with `↑` when they lead up,
+//! `↓` when they lead down and `↕` when they lead both ways.
+//!
+//! ```text
+//! ┌──┬─────┬──┐
+//! │ >│     │██│
+//! │  │  ╶──┼──┤
+//! │↑ │   ↑ │██│
+//! ├──┼──┬──┼──┤
+//! │██│██│██│██│
+//! └──┴──┴──┴──┘
+//!
+//! ┌────────┬──┐
+//! │        │██│
+//! ├──╴  ╷  ├──┤
+//! │↓    │↓E│██│
+//! ├──┬──┼──┼──┤
+//! │██│██│██│██│
+//! └──┴──┴──┴──┘
+//! ```
+//!
+//! Toroidal mazes are written like slim mazes, with `↔` in the top left corner when the
+//! left and right edges are joined, `⇕` for the top and bottom edges and `⊞` for both.
+//! Passages over a joined edge show up as gaps in the outer walls on both sides.
+//!
+//! ```text
+//! ↔─────┬──┐
+//! │ >   │  │
+//! └──╴  │  ╵
+//!       │ E
+//! ╶─────┴──╴
+//! ```
+//!
+//! Weave mazes are written like slim mazes as well, with `╳` in the top left corner. The
+//! first character of a crossing is replaced by `┼`, its under-passage runs through the
+//! two walls of the cell.
+//!
+//! ```text
+//! ╳────────┬──┬──┐
+//! │ >      │  │██│
+//! ├─────┐  │  ├──┤
+//! │     │┼ │  │██│
+//! │  ╶──┤  ╵  ├──┤
+//! │     │     │ E│
+//! ├──┬──┼──┬──┼──┤
+//! │██│██│██│██│██│
+//! └──┴──┴──┴──┴──┘
+//! ```
+//!
+//! A maze is read back with `str::parse`. Text containing `/` or `\` is parsed as a
+//! hexagonal maze and text containing `-` as a polar maze. Text with stair markers, or
+//! with box-drawing characters and an empty line, is parsed as a layered maze. A weave or
+//! wrap marker in the top left corner makes a weave or toroidal maze, other text containing any
+//! box-drawing character is parsed as a slim maze and anything else as a thick one.
+//! Surrounding blank lines are skipped and short lines are padded with spaces, so trailing
+//! whitespace may be trimmed.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    algorithms::{HexDirection, Point, PolarDirection},
+    CellType, Maze, MazeCell, MazeType, SlimWallsCellType, Terrain, Wrap,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeParseError {
+    Empty,
+    /// Lines cannot be split into whole cells. Holds the length of the longest line.
+    InvalidWidth(usize),
+    /// Slim and hexagonal mazes need a wall line above and below every line of cells.
+    MissingWallLine,
+    InvalidCell {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    InvalidWall {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// Levels of a layered maze have different sizes.
+    UnevenLevels,
+}
+
+impl fmt::Display for MazeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeParseError::Empty => write!(f, "Maze text is empty"),
+            MazeParseError::InvalidWidth(width) => {
+                write!(f, "Line width of {} does not fit whole cells", width)
+            }
+            MazeParseError::MissingWallLine => {
+                write!(f, "Maze must start and end with a wall line")
+            }
+            MazeParseError::InvalidCell { line, column, text } => {
+                write!(f, "Unknown cell {:?} at {}:{}", text, line, column)
+            }
+            MazeParseError::InvalidWall { line, column, text } => {
+                write!(f, "Unknown wall {:?} at {}:{}", text, line, column)
+            }
+            MazeParseError::UnevenLevels => {
+                write!(f, "Levels of a layered maze must all have the same size")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MazeParseError {}
+
+fn terrain_char(terrain: Terrain) -> char {
+    match terrain {
+        Terrain::Normal => ' ',
+        Terrain::SwampLite => '░',
+        Terrain::SwampMedium => '▒',
+        Terrain::SwampHeavy => '▓',
+    }
+}
+
+fn terrain_from_char(c: char) -> Option<Terrain> {
+    match c {
+        ' ' => Some(Terrain::Normal),
+        '░' => Some(Terrain::SwampLite),
+        '▒' => Some(Terrain::SwampMedium),
+        '▓' => Some(Terrain::SwampHeavy),
+        _ => None,
+    }
+}
+
+fn cell_text(cell: MazeCell) -> String {
+    let terrain = terrain_char(cell.get_terrain());
+    let letter = match cell.get_type() {
+        CellType::Wall => return "██".to_string(),
+        CellType::Path => terrain,
+        CellType::Entrance => '>',
+        CellType::Exit => 'E',
+        CellType::Visited => 'v',
+        CellType::FinalPath => 'F',
+    };
+    [terrain, letter].iter().collect()
+}
+
+fn cell_from_text(text: &str) -> Option<MazeCell> {
+    let mut chars = text.chars();
+    let (first, second) = (chars.next()?, chars.next()?);
+    if chars.next().is_some() {
+        return None;
+    }
+    let (cell_type, terrain) = match (first, second) {
+        ('█', '█') => (CellType::Wall, Terrain::Normal),
+        (first, second) if first == second => (CellType::Path, terrain_from_char(first)?),
+        (first, '>') => (CellType::Entrance, terrain_from_char(first)?),
+        (first, 'E') => (CellType::Exit, terrain_from_char(first)?),
+        (first, 'v') => (CellType::Visited, terrain_from_char(first)?),
+        (first, 'F') => (CellType::FinalPath, terrain_from_char(first)?),
+        _ => return None,
+    };
+
+    let mut cell = MazeCell::new(cell_type);
+    cell.set_terrain(terrain);
+    Some(cell)
+}
+
+fn junction(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => ' ',
+        (true, false, false, false) => '╵',
+        (false, true, false, false) => '╷',
+        (false, false, true, false) => '╴',
+        (false, false, false, true) => '╶',
+        (true, true, false, false) => '│',
+        (false, false, true, true) => '─',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (false, true, true, true) => '┬',
+        (true, false, true, true) => '┴',
+        (true, true, true, true) => '┼',
+    }
+}
+
+fn is_box_drawing(c: char) -> bool {
+    ('\u{2500}'..='\u{257F}').contains(&c)
+}
+
+/// Top left corner of a weave maze.
+const WEAVE_MARKER: char = '╳';
+/// Replaces the first character of a crossing in a weave maze.
+const CROSSING_MARKER: char = '┼';
+
+/// Top left corner of a toroidal maze.
+fn wrap_marker(wrap: Wrap) -> char {
+    match wrap {
+        Wrap::Horizontal => '↔',
+        Wrap::Vertical => '⇕',
+        Wrap::Both => '⊞',
+    }
+}
+
+/// Marker replacing the first character of a cell with stairs, `None` without stairs.
+fn stair_marker(cell: MazeCell) -> Option<char> {
+    match (cell.has_up_wall(), cell.has_down_wall()) {
+        (false, false) => Some('↕'),
+        (false, true) => Some('↑'),
+        (true, false) => Some('↓'),
+        (true, true) => None,
+    }
+}
+
+/// Whether a stair marker leads up and down.
+fn stairs_from_marker(c: char) -> Option<(bool, bool)> {
+    match c {
+        '↕' => Some((true, true)),
+        '↑' => Some((true, false)),
+        '↓' => Some((false, true)),
+        _ => None,
+    }
+}
+
+/// Restores the first character of a cell that was replaced by a marker.
+fn unmark_cell(line: &mut [char], column: usize) {
+    // Terrain fills both characters of a path, a marker hides that of the other cells
+    line[column] = match line[column + 1] {
+        terrain @ ('░' | '▒' | '▓' | '█') => terrain,
+        _ => ' ',
+    };
+}
+
+/// Moves an error of a parsed block of lines to where the block starts.
+fn shift_error(error: MazeParseError, lines: usize) -> MazeParseError {
+    match error {
+        MazeParseError::InvalidCell { line, column, text } => MazeParseError::InvalidCell {
+            line: line + lines,
+            column,
+            text,
+        },
+        MazeParseError::InvalidWall { line, column, text } => MazeParseError::InvalidWall {
+            line: line + lines,
+            column,
+            text,
+        },
+        error => error,
+    }
+}
+
+/// Line, column and character of a hexagonal cell's wall in the text format.
+fn hex_wall_position(point: Point, direction: HexDirection) -> (usize, usize, char) {
+    // Odd rows are shifted right by half a cell
+    let column = (point.y % 2) * 2 + point.x * 4;
+    let line = point.y * 2;
+    match direction {
+        HexDirection::East => (line + 1, column + 4, '|'),
+        HexDirection::SouthEast => (line + 2, column + 3, '/'),
+        HexDirection::SouthWest => (line + 2, column + 1, '\\'),
+        HexDirection::West => (line + 1, column, '|'),
+        HexDirection::NorthWest => (line, column + 1, '/'),
+        HexDirection::NorthEast => (line, column + 3, '\\'),
+    }
+}
+
+impl Maze {
+    /// Is there a wall on the vertical grid line `column` (0..=width) in row `y`.
+    fn has_vertical_wall(&self, column: usize, y: usize) -> bool {
+        let left = column
+            .checked_sub(1)
+            .map(|x| self.get_cell(Point { x, y }).has_right_wall());
+        let right =
+            (column < self.width).then(|| self.get_cell(Point { x: column, y }).has_left_wall());
+        left.unwrap_or(true) && right.unwrap_or(true)
+    }
+
+    /// Is there a wall on the horizontal grid line `row` (0..=height) in column `x`.
+    fn has_horizontal_wall(&self, x: usize, row: usize) -> bool {
+        let above = row
+            .checked_sub(1)
+            .map(|y| self.get_cell(Point { x, y }).has_bottom_wall());
+        let below = (row < self.height).then(|| self.get_cell(Point { x, y: row }).has_top_wall());
+        above.unwrap_or(true) && below.unwrap_or(true)
+    }
+
+    /// Slim walls with `corner` in place of the top left junction.
+    fn fmt_slim(&self, f: &mut fmt::Formatter<'_>, corner: Option<char>) -> fmt::Result {
+        for row in 0..=self.height {
+            for column in 0..=self.width {
+                let up = row > 0 && self.has_vertical_wall(column, row - 1);
+                let down = row < self.height && self.has_vertical_wall(column, row);
+                let left = column > 0 && self.has_horizontal_wall(column - 1, row);
+                let right = column < self.width && self.has_horizontal_wall(column, row);
+                match corner {
+                    Some(corner) if (row, column) == (0, 0) => write!(f, "{}", corner)?,
+                    _ => write!(f, "{}", junction(up, down, left, right))?,
+                }
+                if right {
+                    write!(f, "──")?;
+                } else if column < self.width {
+                    write!(f, "  ")?;
+                }
+            }
+            writeln!(f)?;
+
+            if row == self.height {
+                break;
+            }
+            for column in 0..=self.width {
+                let wall = if self.has_vertical_wall(column, row) {
+                    "│"
+                } else {
+                    " "
+                };
+                write!(f, "{}", wall)?;
+                if column < self.width {
+                    let cell = self.get_cell(Point { x: column, y: row });
+                    let text = cell_text(cell);
+                    if cell.has_under_passage() {
+                        // Keeps the second character, it tells the cell type apart
+                        let second = text.chars().last().unwrap_or(' ');
+                        write!(f, "{}{}", CROSSING_MARKER, second)?;
+                    } else {
+                        write!(f, "{}", text)?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    /// Neighbour of a hexagonal cell anywhere on the grid, including the unused last
+    /// row and column.
+    fn hex_neighbor(&self, point: Point, direction: HexDirection) -> Option<Point> {
+        let (dx, dy) = direction.offset(point.y);
+        let x = point.x.checked_add_signed(dx as isize)?;
+        let y = point.y.checked_add_signed(dy as isize)?;
+        (x < self.width && y < self.height).then_some(Point { x, y })
+    }
+
+    fn has_hex_edge(&self, point: Point, direction: HexDirection) -> bool {
+        self.get_cell(point).has_hex_wall(direction)
+            && self
+                .hex_neighbor(point, direction)
+                .is_none_or(|next| self.get_cell(next).has_hex_wall(direction.opposite()))
+    }
+
+    fn fmt_hex(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![vec![' '; self.width * 4 + 3]; self.height * 2 + 1];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let point = Point { x, y };
+                for direction in HexDirection::ALL {
+                    if self.has_hex_edge(point, direction) {
+                        let (line, column, wall) = hex_wall_position(point, direction);
+                        lines[line][column] = wall;
+                    }
+                }
+
+                let (line, column, _) = hex_wall_position(point, HexDirection::West);
+                for (offset, c) in cell_text(self.get_cell(point)).chars().enumerate() {
+                    lines[line][column + 2 + offset] = c;
+                }
+            }
+        }
+
+        for line in lines {
+            writeln!(f, "{}", line.iter().collect::<String>().trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn fmt_polar(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            let width = self.ring_width(y);
+            let (mut walls, mut cells) = (String::new(), String::new());
+            for x in 0..width {
+                let cell = self.get_cell(Point { x, y });
+                let previous = self.get_cell(Point {
+                    x: (x + width - 1) % width,
+                    y,
+                });
+                let inward = cell.has_polar_wall(PolarDirection::Inward);
+                let side = cell.has_polar_wall(PolarDirection::CounterClockwise)
+                    && previous.has_polar_wall(PolarDirection::Clockwise);
+
+                walls.push_str(if inward { " --" } else { "   " });
+                cells.push(if side { '|' } else { ' ' });
+                cells.push_str(&cell_text(cell));
+            }
+            writeln!(f, "{}", walls.trim_end())?;
+            writeln!(f, "{}", cells.trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn fmt_layered(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for level in 0..self.levels() {
+            if level > 0 {
+                writeln!(f)?;
+            }
+
+            let text = format!("{:?}", self.level(level));
+            for (index, line) in text.lines().enumerate() {
+                let mut line: Vec<char> = line.chars().collect();
+                if index % 2 == 1 {
+                    let y = level * self.level_height() + index / 2;
+                    for x in 0..self.width {
+                        if let Some(marker) = stair_marker(self.get_cell(Point { x, y })) {
+                            line[x * 3 + 1] = marker;
+                        }
+                    }
+                }
+                writeln!(f, "{}", line.into_iter().collect::<String>())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_thick(lines: &[Vec<char>]) -> Result<Maze, MazeParseError> {
+        let line_width = lines[0].len();
+        if !line_width.is_multiple_of(2) {
+            return Err(MazeParseError::InvalidWidth(line_width));
+        }
+
+        let mut maze = Maze::new(line_width / 2, lines.len(), MazeType::Thick, None);
+        for (y, line) in lines.iter().enumerate() {
+            for x in 0..maze.width {
+                let text: String = line[x * 2..x * 2 + 2].iter().collect();
+                let cell = cell_from_text(&text).ok_or(MazeParseError::InvalidCell {
+                    line: y + 1,
+                    column: x * 2 + 1,
+                    text,
+                })?;
+                maze.set_cell(x, y, cell);
+            }
+        }
+        Ok(maze)
+    }
+
+    fn parse_slim(lines: &[Vec<char>]) -> Result<Maze, MazeParseError> {
+        let line_width = lines[0].len();
+        if line_width % 3 != 1 || line_width < 4 {
+            return Err(MazeParseError::InvalidWidth(line_width));
+        }
+        if lines.len().is_multiple_of(2) || lines.len() < 3 {
+            return Err(MazeParseError::MissingWallLine);
+        }
+
+        let width = line_width / 3;
+        let height = lines.len() / 2;
+        let mut maze = Maze::new(width, height, MazeType::Slim, None);
+
+        for y in 0..height {
+            let cell_line = &lines[y * 2 + 1];
+            for x in 0..width {
+                let text: String = cell_line[x * 3 + 1..x * 3 + 3].iter().collect();
+                let mut cell = cell_from_text(&text).ok_or(MazeParseError::InvalidCell {
+                    line: y * 2 + 2,
+                    column: x * 3 + 2,
+                    text,
+                })?;
+                cell.walls = 0;
+                maze.set_cell(x, y, cell);
+            }
+        }
+
+        // Horizontal walls sit on the even lines, above the row of cells with the same half index
+        for row in 0..=height {
+            let index = row * 2;
+            for x in 0..width {
+                let column = x * 3 + 1;
+                let text: String = lines[index][column..column + 2].iter().collect();
+                match text.as_str() {
+                    "──" => {}
+                    "  " => continue,
+                    _ => {
+                        return Err(MazeParseError::InvalidWall {
+                            line: index + 1,
+                            column: column + 1,
+                            text,
+                        })
+                    }
+                }
+                if row > 0 {
+                    maze.add_wall(Point { x, y: row - 1 }, (0, 1));
+                }
+                if row < height {
+                    maze.add_wall(Point { x, y: row }, (0, -1));
+                }
+            }
+        }
+
+        // Vertical walls sit on the odd lines, to the left of the cell with the same index
+        for y in 0..height {
+            let index = y * 2 + 1;
+            for x in 0..=width {
+                let column = x * 3;
+                match lines[index][column] {
+                    '│' => {}
+                    ' ' => continue,
+                    other => {
+                        return Err(MazeParseError::InvalidWall {
+                            line: index + 1,
+                            column: column + 1,
+                            text: other.to_string(),
+                        })
+                    }
+                }
+                if x > 0 {
+                    maze.add_wall(Point { x: x - 1, y }, (1, 0));
+                }
+                if x < width {
+                    maze.add_wall(Point { x, y }, (-1, 0));
+                }
+            }
+        }
+
+        Ok(maze)
+    }
+
+    fn parse_polar(lines: &[Vec<char>]) -> Result<Maze, MazeParseError> {
+        if !lines.len().is_multiple_of(2) {
+            return Err(MazeParseError::MissingWallLine);
+        }
+
+        // The grid is as wide as the outermost ring, the unused cells of inner rings are walls
+        let mut maze = Maze::new(0, lines.len() / 2, MazeType::Polar, Some(CellType::Wall));
+        let line_width = lines[0].len();
+        if line_width > maze.width * 3 {
+            return Err(MazeParseError::InvalidWidth(line_width));
+        }
+
+        let char_at = |line: usize, column: usize| lines[line].get(column).copied().unwrap_or(' ');
+        let text_at = |line: usize, column: usize| -> String {
+            (column..column + 2)
+                .map(|column| char_at(line, column))
+                .collect()
+        };
+
+        for y in 0..maze.height {
+            let (wall_line, cell_line) = (y * 2, y * 2 + 1);
+            let width = maze.ring_width(y);
+            for x in 0..width {
+                let column = x * 3;
+                let text = text_at(cell_line, column + 1);
+                let mut cell = cell_from_text(&text).ok_or(MazeParseError::InvalidCell {
+                    line: cell_line + 1,
+                    column: column + 2,
+                    text,
+                })?;
+                cell.walls = 0;
+
+                let text = text_at(wall_line, column + 1);
+                match text.as_str() {
+                    "--" => cell.set_polar_wall(PolarDirection::Inward, true),
+                    "  " => {}
+                    _ => {
+                        return Err(MazeParseError::InvalidWall {
+                            line: wall_line + 1,
+                            column: column + 2,
+                            text,
+                        })
+                    }
+                }
+                maze.set_cell(x, y, cell);
+            }
+
+            // A wall counter-clockwise of a cell is also clockwise of the cell before it
+            for x in 0..width {
+                let column = x * 3;
+                match char_at(cell_line, column) {
+                    '|' => {}
+                    ' ' => continue,
+                    other => {
+                        return Err(MazeParseError::InvalidWall {
+                            line: cell_line + 1,
+                            column: column + 1,
+                            text: other.to_string(),
+                        })
+                    }
+                }
+                let previous = Point {
+                    x: (x + width - 1) % width,
+                    y,
+                };
+                maze.add_polar_wall(Point { x, y }, PolarDirection::CounterClockwise);
+                maze.add_polar_wall(previous, PolarDirection::Clockwise);
+            }
+        }
+        Ok(maze)
+    }
+
+    fn parse_hex(lines: &[Vec<char>]) -> Result<Maze, MazeParseError> {
+        let line_width = lines[0].len();
+        if line_width < 5 {
+            return Err(MazeParseError::InvalidWidth(line_width));
+        }
+        if lines.len().is_multiple_of(2) || lines.len() < 3 {
+            return Err(MazeParseError::MissingWallLine);
+        }
+
+        let width = (line_width - 1) / 4;
+        let height = lines.len() / 2;
+        let mut maze = Maze::new(width, height, MazeType::Hex, None);
+        let char_at = |line: usize, column: usize| lines[line].get(column).copied().unwrap_or(' ');
+
+        for y in 0..height {
+            for x in 0..width {
+                let point = Point { x, y };
+                let (line, column, _) = hex_wall_position(point, HexDirection::West);
+                let text: String = (column + 2..column + 4)
+                    .map(|column| char_at(line, column))
+                    .collect();
+                let mut cell = cell_from_text(&text).ok_or(MazeParseError::InvalidCell {
+                    line: line + 1,
+                    column: column + 3,
+                    text,
+                })?;
+
+                // Both cells next to a wall read it, so walls stay symmetric
+                cell.walls = 0;
+                for direction in HexDirection::ALL {
+                    let (line, column, wall) = hex_wall_position(point, direction);
+                    match char_at(line, column) {
+                        ' ' => {}
+                        c if c == wall => cell.set_hex_wall(direction, true),
+                        other => {
+                            return Err(MazeParseError::InvalidWall {
+                                line: line + 1,
+                                column: column + 1,
+                                text: other.to_string(),
+                            })
+                        }
+                    }
+                }
+                maze.set_cell(x, y, cell);
+            }
+        }
+        Ok(maze)
+    }
+
+    /// Every level is parsed as a slim maze after the stair markers are taken out of it.
+    fn parse_layered(lines: &[Vec<char>]) -> Result<Maze, MazeParseError> {
+        let mut levels: Vec<Maze> = Vec::new();
+        // Level, cell, direction up and the position of the marker
+        let mut stairs: Vec<(usize, Point, bool, usize, usize)> = Vec::new();
+
+        let mut start = 0;
+        while start < lines.len() {
+            let is_blank = |line: &Vec<char>| line.iter().all(|c| c.is_whitespace());
+            if is_blank(&lines[start]) {
+                start += 1;
+                continue;
+            }
+            let end = (start..lines.len())
+                .find(|&index| is_blank(&lines[index]))
+                .unwrap_or(lines.len());
+
+            let mut block = lines[start..end].to_vec();
+            for (index, line) in block.iter_mut().enumerate().skip(1).step_by(2) {
+                for column in (1..line.len().saturating_sub(1)).step_by(3) {
+                    let Some((up, down)) = stairs_from_marker(line[column]) else {
+                        continue;
+                    };
+                    unmark_cell(line, column);
+                    let point = Point {
+                        x: column / 3,
+                        y: index / 2,
+                    };
+                    for (is_stair, is_up) in [(up, true), (down, false)] {
+                        if is_stair {
+                            stairs.push((levels.len(), point, is_up, start + index, column));
+                        }
+                    }
+                }
+            }
+
+            let level = Maze::parse_slim(&block).map_err(|error| shift_error(error, start))?;
+            if levels
+                .first()
+                .is_some_and(|first| (first.width, first.height) != (level.width, level.height))
+            {
+                return Err(MazeParseError::UnevenLevels);
+            }
+            levels.push(level);
+            start = end;
+        }
+
+        let (width, height) = (levels[0].width, levels[0].height);
+        let mut maze = Maze::new(width, height, MazeType::Layered(levels.len()), None);
+        for (index, level) in levels.iter().enumerate() {
+            for y in 0..height {
+                for x in 0..width {
+                    let mut cell = level.get_cell(Point { x, y });
+                    cell.set_up_wall(true);
+                    cell.set_down_wall(true);
+                    maze.set_cell(x, index * height + y, cell);
+                }
+            }
+        }
+
+        for (level, point, is_up, line, column) in stairs {
+            let current = Point {
+                x: point.x,
+                y: level * height + point.y,
+            };
+            let next = match (is_up, level + 1 < levels.len(), level > 0) {
+                (true, true, _) => current.y + height,
+                (false, _, true) => current.y - height,
+                _ => {
+                    return Err(MazeParseError::InvalidWall {
+                        line: line + 1,
+                        column: column + 1,
+                        text: lines[line][column].to_string(),
+                    })
+                }
+            };
+            maze.remove_walls_between_cells(
+                current,
+                Point {
+                    x: point.x,
+                    y: next,
+                },
+            );
+        }
+        Ok(maze)
+    }
+
+    fn parse_weave(lines: &[Vec<char>]) -> Result<Maze, MazeParseError> {
+        let mut lines = lines.to_vec();
+        lines[0][0] = ' ';
+
+        let mut crossings = Vec::new();
+        for (index, line) in lines.iter_mut().enumerate().skip(1).step_by(2) {
+            for column in (1..line.len().saturating_sub(1)).step_by(3) {
+                if line[column] == CROSSING_MARKER {
+                    unmark_cell(line, column);
+                    crossings.push(Point {
+                        x: column / 3,
+                        y: index / 2,
+                    });
+                }
+            }
+        }
+
+        let mut maze = Maze::parse_slim(&lines)?;
+        maze.maze_type = MazeType::Weave;
+        for point in crossings {
+            let mut cell = maze.get_cell(point);
+            cell.set_under_passage(true);
+            maze.set_cell(point.x, point.y, cell);
+        }
+        Ok(maze)
+    }
+
+    fn add_wall(&mut self, point: Point, direction: (i32, i32)) {
+        let mut cell = self.get_cell(point);
+        cell.set_wall_by_direction(direction, true);
+        self.set_cell(point.x, point.y, cell);
+    }
+
+    fn add_polar_wall(&mut self, point: Point, direction: PolarDirection) {
+        let mut cell = self.get_cell(point);
+        cell.set_polar_wall(direction, true);
+        self.set_cell(point.x, point.y, cell);
+    }
+}
+
+impl fmt::Debug for Maze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.maze_type {
+            MazeType::Thick => {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        write!(f, "{}", cell_text(self.get_cell(Point { x, y })))?;
+                    }
+                    writeln!(f)?;
+                }
+                Ok(())
+            }
+            MazeType::Slim => self.fmt_slim(f, None),
+            MazeType::Toroidal(wrap) => self.fmt_slim(f, Some(wrap_marker(wrap))),
+            MazeType::Weave => self.fmt_slim(f, Some(WEAVE_MARKER)),
+            MazeType::Hex => self.fmt_hex(f),
+            MazeType::Polar => self.fmt_polar(f),
+            MazeType::Layered(_) => self.fmt_layered(f),
+        }
+    }
+}
+
+impl FromStr for Maze {
+    type Err = MazeParseError;
+
+    /// Parses the text format described in the module documentation. The parsed cells
+    /// are also backed up as the original maze.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+        while lines.first().is_some_and(|line| line.is_empty()) {
+            lines.remove(0);
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        if lines.is_empty() {
+            return Err(MazeParseError::Empty);
+        }
+
+        let line_width = lines
+            .iter()
+            .map(|line| line.len())
+            .max()
+            .unwrap_or_default();
+        for line in lines.iter_mut() {
+            line.resize(line_width, ' ');
+        }
+
+        let mut maze = if lines.iter().flatten().any(|&c| c == '/' || c == '\\') {
+            Maze::parse_hex(&lines)?
+        } else if lines.iter().flatten().any(|&c| c == '-') {
+            Maze::parse_polar(&lines)?
+        } else if lines
+            .iter()
+            .flatten()
+            .any(|&c| stairs_from_marker(c).is_some())
+            || lines.iter().flatten().any(|&c| is_box_drawing(c))
+                && lines
+                    .iter()
+                    .any(|line| line.iter().all(|c| c.is_whitespace()))
+        {
+            Maze::parse_layered(&lines)?
+        } else if lines[0][0] == WEAVE_MARKER {
+            Maze::parse_weave(&lines)?
+        } else if let Some(wrap) = [Wrap::Horizontal, Wrap::Vertical, Wrap::Both]
+            .into_iter()
+            .find(|&wrap| lines[0][0] == wrap_marker(wrap))
+        {
+            lines[0][0] = ' ';
+            let mut maze = Maze::parse_slim(&lines)?;
+            maze.maze_type = MazeType::Toroidal(wrap);
+            maze
+        } else if lines.iter().flatten().any(|&c| is_box_drawing(c)) {
+            Maze::parse_slim(&lines)?
+        } else {
+            Maze::parse_thick(&lines)?
+        };
+        maze.backup();
+        Ok(maze)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::algorithms::{AStar, Backtracking, MazeGenerationAlgorithm, PathfindingAlgorithm};
+
+    fn assert_same_cells(left: &Maze, right: &Maze) {
+        assert_eq!((left.width, left.height), (right.width, right.height));
+        for y in 0..left.height {
+            for x in 0..left.width {
+                let point = Point { x, y };
+                assert_eq!(
+                    left.get_cell(point),
+                    right.get_cell(point),
+                    "at {:?}",
+                    point
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_thick() {
+        let maze: Maze = "
+██████████
+██ >░░  ██
+██████  ██
+██ E    ██
+██████████
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Thick);
+        assert_eq!((maze.width, maze.height), (5, 5));
+        assert_eq!(maze.get_entrance(), Some(Point { x: 1, y: 1 }));
+        assert_eq!(maze.get_exit(), Some(Point { x: 1, y: 3 }));
+        assert_eq!(
+            maze.get_cost(Point { x: 2, y: 1 }),
+            Terrain::SwampLite.cost()
+        );
+    }
+
+    #[test]
+    fn test_parse_slim() {
+        let maze: Maze = "
+┌──┬─────┐
+│ >│     │
+│  ╵  ╶──┘
+│       E
+└────────╴
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Slim);
+        assert_eq!((maze.width, maze.height), (3, 2));
+        assert!(maze.get_cell(Point { x: 0, y: 0 }).has_right_wall());
+        assert!(!maze.get_cell(Point { x: 0, y: 0 }).has_bottom_wall());
+        assert!(maze.get_cell(Point { x: 2, y: 0 }).has_bottom_wall());
+        assert!(!maze.get_cell(Point { x: 2, y: 1 }).has_right_wall());
+        assert_eq!(maze.get_exit(), Some(Point { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        let maze: Maze = r"
+ / \ / \ / \
+|  >      ░░|
+ \   \   \ / \
+  |   |      E|
+   \ / \ / \ /
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Hex);
+        assert_eq!((maze.width, maze.height), (3, 2));
+        let cell = maze.get_cell(Point { x: 0, y: 0 });
+        assert!(!cell.has_hex_wall(HexDirection::East));
+        assert!(!cell.has_hex_wall(HexDirection::SouthEast));
+        assert!(cell.has_hex_wall(HexDirection::SouthWest));
+        assert!(maze
+            .get_cell(Point { x: 0, y: 1 })
+            .has_hex_wall(HexDirection::East));
+        assert_eq!(maze.get_exit(), Some(Point { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn test_parse_polar() {
+        let maze: Maze = "
+ --
+| >
+    -- --    -- --
+|        |
+       --       --    -- --       --
+|  |     |  |    E|        |  |
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Polar);
+        assert_eq!((maze.ring_width(1), maze.height), (6, 3));
+        assert_eq!(maze.get_entrance(), Some(Point { x: 0, y: 0 }));
+        assert!(maze.is_passable(Point { x: 0, y: 0 }, Point { x: 0, y: 1 }));
+        assert!(!maze.is_passable(Point { x: 0, y: 0 }, Point { x: 1, y: 1 }));
+        // The wall between the last and the first cell of a ring
+        assert!(!maze.is_passable(Point { x: 5, y: 1 }, Point { x: 0, y: 1 }));
+        assert!(maze.is_passable(Point { x: 10, y: 2 }, Point { x: 5, y: 1 }));
+    }
+
+    #[test]
+    fn test_parse_layered() {
+        let maze: Maze = "
+┌──┬─────┐
+│ >│     │
+│  │  ╶──┤
+│↑ │   ↑ │
+└──┴─────┘
+
+┌────────┐
+│        │
+├──╴  ╷  │
+│↓    │↓E│
+└─────┴──┘
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Layered(2));
+        assert_eq!((maze.width, maze.level_height()), (3, 2));
+        assert!(maze.is_passable(Point { x: 0, y: 1 }, Point { x: 0, y: 3 }));
+        assert!(maze.is_passable(Point { x: 2, y: 3 }, Point { x: 2, y: 1 }));
+        assert!(!maze.is_passable(Point { x: 1, y: 1 }, Point { x: 1, y: 3 }));
+        assert_eq!(maze.get_exit(), Some(Point { x: 2, y: 3 }));
+        assert_eq!(
+            "┌──┐\n│↓ │\n└──┘".parse::<Maze>().unwrap_err(),
+            MazeParseError::InvalidWall {
+                line: 2,
+                column: 2,
+                text: "↓".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_toroidal() {
+        let maze: Maze = "
+↔─────┬──┐
+│ >   │  │
+└──╴  │  ╵
+      │ E
+╶─────┴──╴
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Toroidal(Wrap::Horizontal));
+        assert_eq!((maze.width, maze.height), (3, 2));
+        assert!(!maze.is_passable(Point { x: 0, y: 0 }, Point { x: 2, y: 0 }));
+        assert!(maze.is_passable(Point { x: 2, y: 1 }, Point { x: 0, y: 1 }));
+        assert_eq!(maze.get_exit(), Some(Point { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn test_parse_weave() {
+        let maze: Maze = "
+╳────────┬──┐
+│ >      │  │
+├─────┐  │  │
+│     │┼ │ E│
+└─────┴──┴──┘
+"
+        .parse()
+        .unwrap();
+
+        assert_eq!(maze.maze_type, MazeType::Weave);
+        assert!(maze
+            .get_cell(Point { x: 2, y: 1 })
+            .has_under_passage_along((1, 0)));
+        assert!(maze.is_passable(Point { x: 1, y: 1 }, Point { x: 3, y: 1 }));
+        assert!(!maze.is_passable(Point { x: 1, y: 1 }, Point { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn test_debug_round_trip() {
+        for maze_type in MazeType::cell_types() {
+            let maze = Backtracking::default()
+                .generate(maze_type, 11, 11, Point::default(), 42, None)
+                .unwrap();
+            let parsed: Maze = format!("{:?}", maze).parse().unwrap();
+
+            assert_eq!(parsed.maze_type, maze_type);
+            assert_same_cells(&maze, &parsed);
+        }
+    }
+
+    #[test]
+    fn test_searched_terrain_round_trip() {
+        for maze_type in [
+            MazeType::Thick,
+            MazeType::Slim,
+            MazeType::Hex,
+            MazeType::Polar,
+        ] {
+            let mut maze = Backtracking::default()
+                .generate(maze_type, 11, 11, Point::default(), 7, None)
+                .unwrap();
+            maze.scatter_terrain(&mut StdRng::seed_from_u64(7), 1.0);
+            maze.set_terrain(maze.get_exit().unwrap(), Terrain::SwampHeavy);
+            let (sender, _receiver) = channel();
+            AStar::new().find_path(&mut maze, &sender).unwrap();
+            assert!((0..maze.height)
+                .flat_map(|y| (0..maze.width).map(move |x| Point { x, y }))
+                .map(|point| maze.get_cell(point))
+                .any(|cell| cell.get_type() == CellType::FinalPath
+                    && cell.get_terrain() != Terrain::Normal));
+
+            let parsed: Maze = format!("{:?}", maze).parse().unwrap();
+            assert_same_cells(&maze, &parsed);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("\n\n".parse::<Maze>().unwrap_err(), MazeParseError::Empty);
+        assert_eq!(
+            "███".parse::<Maze>().unwrap_err(),
+            MazeParseError::InvalidWidth(3)
+        );
+        assert_eq!(
+            "████\n██ ?".parse::<Maze>().unwrap_err(),
+            MazeParseError::InvalidCell {
+                line: 2,
+                column: 3,
+                text: " ?".to_string()
+            }
+        );
+        assert_eq!(
+            "┌──┐\n│  │".parse::<Maze>().unwrap_err(),
+            MazeParseError::MissingWallLine
+        );
+    }
+}
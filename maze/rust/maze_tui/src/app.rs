@@ -1,18 +1,13 @@
-use std::{
-    collections::VecDeque,
-    error,
-    sync::mpsc::{self, Receiver, Sender},
-    thread,
-};
+use std::error;
 
 use enum_iterator::{next_cycle, previous_cycle};
 use maze_lib::{
     algorithms::{
-        self, Algorithm, BellmanFord, MazeGenerationAlgorithm, PathfindingAlgorithm,
-        PathfindingResult, PathfindingState, Point, DFS,
+        Algorithm, Budget, FramePump, PathfindingError, PathfindingOutcome, PathfindingState, Point,
     },
-    Maze,
+    Mask, Maze, MazeType,
 };
+use rand::{rngs::StdRng, SeedableRng};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
@@ -21,39 +16,92 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
-use crate::{animation::AnimationState, maze_grid::MazeGrid};
+use crate::{animation::AnimationState, endless::EndlessMaze, maze_grid::MazeGrid};
+
+/// Ticks between two scrolled rows of the endless maze
+const ENDLESS_SCROLL_TICKS: usize = 5;
+/// Levels of the layered mazes
+const LAYERED_LEVELS: usize = 3;
+/// Mixed into the seed for braiding, so it reproduces with the maze but does not repeat
+/// the generator's random choices
+const BRAID_SEED: u64 = 0x6272_6169_6400;
+/// Mixed into the seed for scattering terrain, for the same reason
+const TERRAIN_SEED: u64 = 0x7377_616d_7000;
 
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
 #[derive(Debug, Default)]
 pub struct App {
     maze: Maze,
+    maze_type: MazeType,
+    /// Shape of the generated mazes, the whole rectangle without one
+    mask: Option<Mask>,
+    /// Displayed level of a layered maze
+    level: usize,
     selected_algorithm: Algorithm,
-    animation_steps: VecDeque<Maze>,
+    generator: Algorithm,
+    /// Running search, advanced an expansion per tick or per `step_path`
+    pump: Option<FramePump<PathfindingOutcome>>,
+    /// Budget of the running search, cancelled by `stop_search`
+    budget: Budget,
     pub running: bool,
     pathfinding_state: PathfindingState,
+    /// Result of the last search, shown once it is finished
+    outcome: Option<PathfindingOutcome>,
+    /// Why the last generator or search failed, cleared by the next one that succeeds
+    error: Option<PathfindingError>,
     animation_state: AnimationState,
+    seed: u64,
+    endless: Option<EndlessMaze>,
+    ticks: usize,
 }
 
 impl App {
     pub fn new() -> Self {
-        let mut dfs = DFS::new();
-        let maze = dfs
-            .generate(
-                maze_lib::MazeType::Thick,
-                41,
-                41,
-                Point { x: 1, y: 1 },
-                None,
-            )
-            .unwrap();
-        App {
-            maze,
+        Self::with_seed(rand::random())
+    }
+
+    /// Starts with the maze generated from `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        let generator = Algorithm::DFS;
+        let mut app = App {
+            maze: Maze::default(),
+            maze_type: MazeType::Thick,
+            mask: None,
+            level: 0,
+            seed,
+            generator,
+            endless: None,
+            ticks: 0,
             selected_algorithm: Algorithm::default(),
-            animation_steps: VecDeque::new(),
+            pump: None,
+            budget: Budget::new(),
             running: true,
             pathfinding_state: PathfindingState::default(),
+            outcome: None,
+            error: None,
             animation_state: AnimationState::default(),
+        };
+        app.regenerate();
+        app
+    }
+
+    /// Shapes the maze after `mask` from now on. Generators that cannot follow a mask
+    /// fall back to the first one that can.
+    pub fn set_mask(&mut self, mask: Mask) {
+        self.mask = Some(mask);
+        let generators = self.generators();
+        if !generators.contains(&self.generator) {
+            self.generator = generators[0];
+        }
+        self.regenerate();
+    }
+
+    /// Generators that can build the current maze type, in the shape of the mask if any.
+    fn generators(&self) -> Vec<Algorithm> {
+        match self.mask {
+            Some(_) => Algorithm::masked_generation_algorithms(self.maze_type),
+            None => Algorithm::maze_generation_algorithms(self.maze_type),
         }
     }
 
@@ -62,75 +110,221 @@ impl App {
     }
 
     pub fn reset_maze(&mut self) {
-        self.animation_steps.clear();
-        let mut dfs = DFS::new();
-        self.maze = dfs
-            .generate(
-                maze_lib::MazeType::Thick,
-                41,
-                41,
-                Point { x: 1, y: 1 },
-                None,
-            )
-            .unwrap();
+        self.pump = None;
+        self.endless = None;
+        self.seed = rand::random();
+        self.regenerate();
         self.animation_state = AnimationState::default();
         self.pathfinding_state = PathfindingState::default();
     }
 
-    pub fn find_path(&mut self) {
-        self.animation_steps.clear();
+    /// Rebuilds the maze with the current settings. The maze is kept if the generator
+    /// fails, the error is shown instead.
+    fn regenerate(&mut self) {
+        match App::generate(
+            self.generator,
+            self.maze_type,
+            self.mask.as_ref(),
+            self.seed,
+        ) {
+            Ok(maze) => {
+                self.maze = maze;
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn generate(
+        generator: Algorithm,
+        maze_type: MazeType,
+        mask: Option<&Mask>,
+        seed: u64,
+    ) -> Result<Maze, PathfindingError> {
+        let mut generator = generator
+            .get_maze_generator()
+            .ok_or(PathfindingError::InvalidMaze)?;
+        if let Some(mask) = mask {
+            return generator.generate_masked(maze_type, mask, seed, None);
+        }
+
+        // Levels of a layered maze take as much room as a thick maze of twice the size
+        let (size, entrance) = match maze_type {
+            MazeType::Layered(_) => (21, Point { x: 0, y: 0 }),
+            _ => (41, Point { x: 1, y: 1 }),
+        };
+
+        generator.generate(maze_type, size, size, entrance, seed, None)
+    }
+
+    /// Switches between thick walls and layered mazes, keeping the seed. Generators that
+    /// cannot build the new maze type fall back to the first one that can.
+    pub fn toggle_layered(&mut self) {
+        // Layered mazes cannot be shaped by a mask
+        if self.mask.is_some() {
+            return;
+        }
+
+        self.maze_type = match self.maze_type {
+            MazeType::Layered(_) => MazeType::Thick,
+            _ => MazeType::Layered(LAYERED_LEVELS),
+        };
+        let generators = self.generators();
+        if !generators.contains(&self.generator) {
+            self.generator = generators[0];
+        }
+
+        self.pump = None;
+        self.endless = None;
+        self.level = 0;
+        self.regenerate();
+        self.animation_state = AnimationState::default();
+        self.pathfinding_state = PathfindingState::default();
+    }
+
+    pub fn level_up(&mut self) {
+        self.level = (self.level + 1).min(self.maze.levels() - 1);
+    }
+
+    pub fn level_down(&mut self) {
+        self.level = self.level.saturating_sub(1);
+    }
+
+    /// Switches to the next generator and rebuilds the maze from the same seed, so the
+    /// generators (e.g. the growing tree policies) can be compared on equal terms.
+    pub fn select_next_generator(&mut self) {
+        let generators = self.generators();
+        let index = generators
+            .iter()
+            .position(|&generator| generator == self.generator)
+            .map_or(0, |index| (index + 1) % generators.len());
+        self.generator = generators[index];
+
+        self.pump = None;
+        self.endless = None;
+        self.regenerate();
+        self.animation_state = AnimationState::default();
+        self.pathfinding_state = PathfindingState::default();
+    }
+
+    pub fn add_terrain(&mut self) {
+        self.pump = None;
         self.maze.reset();
-        self.pathfinding_state = PathfindingState::Running;
+        self.maze
+            .scatter_terrain(&mut StdRng::seed_from_u64(self.seed ^ TERRAIN_SEED), 0.15);
+        self.maze.backup();
+        self.animation_state = AnimationState::default();
+        self.pathfinding_state = PathfindingState::default();
+    }
 
-        let (sender, receiver): (Sender<PathfindingResult>, Receiver<PathfindingResult>) =
-            mpsc::channel();
+    /// Opens half of the dead ends, so the pathfinders have more than one route to choose.
+    pub fn braid(&mut self) {
+        self.pump = None;
+        self.maze.reset();
+        self.maze
+            .braid(&mut StdRng::seed_from_u64(self.seed ^ BRAID_SEED), 0.5);
+        self.maze.backup();
+        self.animation_state = AnimationState::default();
+        self.pathfinding_state = PathfindingState::default();
+    }
 
-        let selected_algorithm = self.selected_algorithm;
-        let mut maze = self.maze.clone();
-        let handle = thread::spawn(move || match selected_algorithm {
-            Algorithm::AStar => {
-                let mut astar = algorithms::AStar::new();
-                astar.find_path(&mut maze, &sender)
-            }
-            Algorithm::Backtracking => {
-                let mut backtracking = algorithms::Backtracking::new();
-                backtracking.find_path(&mut maze, &sender);
-            }
-            Algorithm::BellmanFord => {
-                let mut bellman_ford = BellmanFord;
-                bellman_ford.find_path(&mut maze, &sender);
-            }
-            Algorithm::BFS => {
-                let mut bfs = algorithms::BFS::new();
-                bfs.find_path(&mut maze, &sender);
-            }
-            Algorithm::DFS => {
-                let mut dfs = algorithms::DFS::new();
-                dfs.find_path(&mut maze, &sender);
-            }
-            Algorithm::Dijkstra => {
-                let mut dijktra = algorithms::Dijkstra::new();
-                dijktra.find_path(&mut maze, &sender);
-            }
-            _ => {}
-        });
+    pub fn toggle_endless(&mut self) {
+        // The endless maze scrolls a thick walls maze of the full width
+        if self.maze_type != MazeType::Thick || self.mask.is_some() {
+            return;
+        }
 
-        while let Ok(received_result) = receiver.recv() {
+        self.endless = match self.endless {
+            Some(_) => None,
+            None => Some(EndlessMaze::new(
+                self.maze.width / 2,
+                self.maze.height,
+                self.seed,
+            )),
+        };
+    }
+
+    pub fn find_path(&mut self) {
+        self.pump = None;
+        if self.start_search() {
             self.animation_state = AnimationState::Running;
-            self.animation_steps.push_back(received_result.maze);
         }
+    }
+
+    /// Advances the search by a single expansion and pauses its animation, starting a search
+    /// if none is running.
+    pub fn step_path(&mut self) {
+        if self.pump.is_none() && !self.start_search() {
+            return;
+        }
+        self.animation_state = AnimationState::Paused;
+        self.advance_search();
+    }
+
+    /// Cuts the running search off, its outcome so far is shown.
+    pub fn stop_search(&mut self) {
+        if self.pump.is_some() {
+            self.budget.cancel();
+            self.finish_search();
+        }
+    }
+
+    /// Starts searching the maze, the frames are pulled by `advance_search`.
+    fn start_search(&mut self) -> bool {
+        if self.endless.is_some() {
+            return false;
+        }
+        let Some(pathfinder) = self.selected_algorithm.get_pathfinder() else {
+            return false;
+        };
 
-        handle.join().expect("Failed to join thread");
+        self.maze.reset();
+        self.outcome = None;
+        self.error = None;
+        self.pathfinding_state = PathfindingState::Running;
+        self.budget = Budget::new();
+        self.pump = Some(FramePump::find_path_within(
+            pathfinder,
+            self.maze.clone(),
+            self.budget.clone(),
+        ));
+        true
+    }
+
+    fn advance_search(&mut self) {
+        let Some(pump) = self.pump.as_mut() else {
+            return;
+        };
+        if let Some(frame) = pump.step() {
+            frame.apply(&mut self.maze);
+        }
+        if pump.is_done() {
+            self.finish_search();
+        }
+    }
+
+    fn finish_search(&mut self) {
+        if let Some(pump) = self.pump.take() {
+            match pump.finish() {
+                Ok(outcome) => self.outcome = Some(outcome),
+                Err(error) => self.error = Some(error),
+            }
+        }
+        self.animation_state = AnimationState::default();
         self.pathfinding_state = PathfindingState::Finished;
     }
 
     pub fn tick(&mut self) {
-        if self.animation_state == AnimationState::Running {
-            if !self.animation_steps.is_empty() {
-                self.maze = self.animation_steps.pop_front().unwrap();
-            } else {
-                self.animation_state = AnimationState::default();
+        self.ticks += 1;
+        if let Some(endless) = self.endless.as_mut() {
+            if self.ticks.is_multiple_of(ENDLESS_SCROLL_TICKS) {
+                endless.scroll();
             }
+            return;
+        }
+
+        if self.animation_state == AnimationState::Running {
+            self.advance_search();
         }
     }
 
@@ -160,10 +354,25 @@ impl Widget for &App {
             .direction(Direction::Horizontal)
             .constraints(vec![Constraint::Percentage(80), Constraint::Percentage(20)])
             .split(area);
-        MazeGrid::new(&self.maze, self.pathfinding_state, self.animation_state)
-            .render(layout[0], buf);
+        let maze = match &self.endless {
+            Some(endless) => endless.maze(),
+            None => &self.maze,
+        };
+        MazeGrid::new(
+            maze,
+            self.level,
+            self.pathfinding_state,
+            self.animation_state,
+            self.seed,
+        )
+        .render(layout[0], buf);
 
-        let algs = Algorithm::pathfinding_algorithms()
+        let side_panel = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(layout[1]);
+
+        let mut algs = Algorithm::pathfinding_algorithms()
             .into_iter()
             .map(|alg| {
                 if alg == self.selected_algorithm {
@@ -173,8 +382,55 @@ impl Widget for &App {
                 }
             })
             .collect::<Vec<Line>>();
+        if let (PathfindingState::Finished, Some(outcome)) = (self.pathfinding_state, &self.outcome)
+        {
+            algs.push(Line::from(""));
+            if let Some(partial) = &outcome.stopped {
+                algs.push(Line::styled(
+                    PathfindingError::Stopped(partial.reason).to_string(),
+                    Style::new().fg(Color::Yellow),
+                ));
+                algs.push(Line::from(format!(
+                    "Visited: {} cells",
+                    partial.visited.len()
+                )));
+                algs.push(Line::from(format!("Frames: {}", partial.frames)));
+            } else if outcome.reachable {
+                algs.push(Line::from(format!("Path: {} cells", outcome.path.len())));
+                algs.push(Line::from(format!("Cost: {}", outcome.cost)));
+            } else {
+                algs.push(Line::styled(
+                    PathfindingError::Unreachable.to_string(),
+                    Style::new().fg(Color::Red),
+                ));
+            }
+            algs.push(Line::from(format!("Expanded: {}", outcome.expanded)));
+        }
+        if let Some(error) = self.error {
+            algs.push(Line::from(""));
+            algs.push(Line::styled(error.to_string(), Style::new().fg(Color::Red)));
+        }
         Paragraph::new(algs)
             .block(Block::default().title("Algorithms").borders(Borders::ALL))
-            .render(layout[1], buf);
+            .render(side_panel[0], buf);
+
+        let generators = self
+            .generators()
+            .into_iter()
+            .map(|generator| {
+                if generator == self.generator {
+                    Line::styled(generator.to_string(), Style::new().fg(Color::Green))
+                } else {
+                    Line::from(generator.to_string())
+                }
+            })
+            .collect::<Vec<Line>>();
+        Paragraph::new(generators)
+            .block(
+                Block::default()
+                    .title("Generators (g)")
+                    .borders(Borders::ALL),
+            )
+            .render(side_panel[1], buf);
     }
 }
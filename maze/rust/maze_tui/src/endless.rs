@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use maze_lib::{
+    algorithms::{Eller, EllerRows, Point},
+    CellType, Maze, MazeType, SlimWallsCellType,
+};
+
+/// Endlessly scrolling thick maze generated row by row with Eller's algorithm. Only the
+/// visible lines are kept, so it can scroll forever in constant memory.
+#[derive(Debug)]
+pub struct EndlessMaze {
+    rows: EllerRows,
+    /// Visible lines of the thick maze, `true` marks a wall
+    lines: VecDeque<Vec<bool>>,
+    /// The visible lines as a thick maze, rebuilt whenever the maze scrolls
+    maze: Maze,
+    width: usize,
+    height: usize,
+}
+
+impl EndlessMaze {
+    /// Maze `width` cells wide (`width * 2 + 1` thick cells) showing `height` lines.
+    pub fn new(width: usize, height: usize, seed: u64) -> Self {
+        let mut endless = EndlessMaze {
+            rows: Eller::rows(width, seed),
+            lines: VecDeque::from([vec![true; width * 2 + 1]]),
+            maze: Maze::default(),
+            width: width * 2 + 1,
+            height,
+        };
+        while endless.lines.len() < height {
+            endless.push_row();
+        }
+        endless.maze = endless.build_maze();
+        endless
+    }
+
+    pub fn scroll(&mut self) {
+        self.push_row();
+        while self.lines.len() > self.height {
+            self.lines.pop_front();
+        }
+        self.maze = self.build_maze();
+    }
+
+    /// The visible part as a thick maze.
+    pub fn maze(&self) -> &Maze {
+        &self.maze
+    }
+
+    fn build_maze(&self) -> Maze {
+        let mut maze = Maze::new(
+            self.width,
+            self.lines.len(),
+            MazeType::Thick,
+            Some(CellType::Wall),
+        );
+        for (y, line) in self.lines.iter().enumerate() {
+            for (x, &is_wall) in line.iter().enumerate() {
+                if !is_wall {
+                    maze.mark_cell_as_path(Point { x, y });
+                }
+            }
+        }
+        maze
+    }
+
+    fn push_row(&mut self) {
+        let row = self.rows.next().expect("Eller's rows never end");
+
+        let mut cells = vec![true; self.width];
+        let mut below = vec![true; self.width];
+        for (index, cell) in row.iter().enumerate() {
+            let x = index * 2 + 1;
+            cells[x] = false;
+            cells[x + 1] = cell.has_right_wall();
+            below[x] = cell.has_bottom_wall();
+        }
+
+        self.lines.push_back(cells);
+        self.lines.push_back(below);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_wall(maze: &Maze, x: usize, y: usize) -> bool {
+        maze.get_cell(Point { x, y }).get_type() == CellType::Wall
+    }
+
+    #[test]
+    fn test_scrolled_rows_are_consistent() {
+        let mut endless = EndlessMaze::new(8, 11, 5);
+        for _ in 0..50 {
+            let previous = endless.maze().clone();
+            endless.scroll();
+            let maze = endless.maze();
+            assert_eq!((maze.width, maze.height), (17, 11));
+
+            // Lines move up by one row of cells and the line below it
+            for y in 0..maze.height - 2 {
+                for x in 0..maze.width {
+                    assert_eq!(is_wall(maze, x, y), is_wall(&previous, x, y + 2));
+                }
+            }
+
+            for y in 0..maze.height {
+                assert!(is_wall(maze, 0, y) && is_wall(maze, maze.width - 1, y));
+                for x in 0..maze.width {
+                    match (x % 2, y % 2) {
+                        // Corners between cells are always walls, the cells never are
+                        (0, 0) => assert!(is_wall(maze, x, y)),
+                        (1, 1) => assert!(!is_wall(maze, x, y)),
+                        // A passage between two rows leads into a cell on both ends
+                        (1, 0) if !is_wall(maze, x, y) => {
+                            assert!(y == 0 || !is_wall(maze, x, y - 1));
+                            assert!(y == maze.height - 1 || !is_wall(maze, x, y + 1));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -16,6 +16,15 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
         KeyCode::Up | KeyCode::Char('k') => app.select_previous_algorithm(),
         KeyCode::Down | KeyCode::Char('j') => app.select_next_algorithm(),
         KeyCode::Enter => app.find_path(),
+        KeyCode::Char('s') | KeyCode::Char('S') => app.step_path(),
+        KeyCode::Char('x') | KeyCode::Char('X') => app.stop_search(),
+        KeyCode::Char('t') | KeyCode::Char('T') => app.add_terrain(),
+        KeyCode::Char('b') | KeyCode::Char('B') => app.braid(),
+        KeyCode::Char('e') | KeyCode::Char('E') => app.toggle_endless(),
+        KeyCode::Char('g') | KeyCode::Char('G') => app.select_next_generator(),
+        KeyCode::Char('l') | KeyCode::Char('L') => app.toggle_layered(),
+        KeyCode::PageUp | KeyCode::Char(']') => app.level_up(),
+        KeyCode::PageDown | KeyCode::Char('[') => app.level_down(),
         KeyCode::Char(' ') => app.pause_unpause_animation(),
         _ => {}
     }
@@ -1,5 +1,6 @@
 pub mod animation;
 pub mod app;
+pub mod endless;
 pub mod event;
 pub mod handler;
 pub mod maze_grid;
@@ -1,4 +1,4 @@
-use std::io;
+use std::{env, io};
 
 use maze_tui::{
     app::{App, AppResult},
@@ -6,11 +6,32 @@ use maze_tui::{
     handler::handle_key_events,
     tui::Tui,
 };
+use maze_lib::Mask;
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
-    let mut app = App::new();
+    // Pass a seed to regenerate a known maze and `--mask <file>` to shape it after a PNG
+    // or text mask
+    let mut seed = None;
+    let mut mask = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mask" => {
+                let path = args.next().ok_or("--mask needs a file")?;
+                mask = Some(Mask::open(path)?);
+            }
+            _ => seed = Some(arg.parse()?),
+        }
+    }
+    let mut app = match seed {
+        Some(seed) => App::with_seed(seed),
+        None => App::new(),
+    };
+    if let Some(mask) = mask {
+        app.set_mask(mask);
+    }
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(20);
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use maze_lib::{
     algorithms::{PathfindingState, Point},
-    CellType, Maze,
+    CellType, Maze, MazeCell, MazeType, SlimWallsCellType, Terrain,
 };
 use ratatui::{buffer::Buffer, prelude::Rect};
 use ratatui::{
@@ -12,17 +14,101 @@ use crate::animation::AnimationState;
 
 pub struct MazeGrid {
     maze: Maze,
+    /// Displayed level and number of levels of a layered maze
+    level: Option<(usize, usize)>,
+    /// Stair markers of the displayed level
+    stairs: HashMap<Point, &'static str>,
     state: PathfindingState,
     animation_state: AnimationState,
+    seed: u64,
 }
 
 impl MazeGrid {
-    pub fn new(maze: &Maze, state: PathfindingState, animation_state: AnimationState) -> Self {
+    /// Layered mazes only show `level`, the other maze types are shown whole.
+    pub fn new(
+        maze: &Maze,
+        level: usize,
+        state: PathfindingState,
+        animation_state: AnimationState,
+        seed: u64,
+    ) -> Self {
+        let (grid, level, stairs) = match maze.maze_type {
+            MazeType::Layered(_) => {
+                let level = level.min(maze.levels() - 1);
+                let (grid, stairs) = MazeGrid::thick_level(&maze.level(level));
+                (grid, Some((level, maze.levels())), stairs)
+            }
+            _ => (maze.clone(), None, HashMap::new()),
+        };
+
         MazeGrid {
-            maze: maze.clone(),
+            maze: grid,
+            level,
+            stairs,
             state,
             animation_state,
+            seed,
+        }
+    }
+
+    /// Draws a level of a layered maze the same way as a thick walls maze, every wall
+    /// becomes a wall cell. Also returns the stair markers of the level.
+    fn thick_level(level: &Maze) -> (Maze, HashMap<Point, &'static str>) {
+        // The last row and column of a level are never used
+        let (width, height) = (level.width - 1, level.height - 1);
+        let mut grid = Maze::new(
+            width * 2 + 1,
+            height * 2 + 1,
+            MazeType::Thick,
+            Some(CellType::Wall),
+        );
+        let mut stairs = HashMap::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = level.get_cell(Point { x, y });
+                let point = Point {
+                    x: x * 2 + 1,
+                    y: y * 2 + 1,
+                };
+                grid.set_cell(point.x, point.y, cell);
+
+                let marker = match (cell.has_up_wall(), cell.has_down_wall()) {
+                    (false, false) => Some("↕↕"),
+                    (false, true) => Some("↑↑"),
+                    (true, false) => Some("↓↓"),
+                    (true, true) => None,
+                };
+                if let Some(marker) = marker {
+                    stairs.insert(point, marker);
+                }
+
+                let openings = [
+                    (
+                        x + 1 < width && !cell.has_right_wall(),
+                        Point { x: x + 1, y },
+                    ),
+                    (
+                        y + 1 < height && !cell.has_bottom_wall(),
+                        Point { x, y: y + 1 },
+                    ),
+                ];
+                for (is_open, next) in openings {
+                    if !is_open {
+                        continue;
+                    }
+                    // Passages between two visited cells are drawn as visited too
+                    let next_cell = level.get_cell(next);
+                    let passage = if next_cell.get_type() == cell.get_type() {
+                        cell
+                    } else {
+                        MazeCell::new(CellType::Path)
+                    };
+                    grid.set_cell(x + next.x + 1, y + next.y + 1, passage);
+                }
+            }
         }
+        (grid, stairs)
     }
 }
 
@@ -42,8 +128,12 @@ impl Widget for MazeGrid {
             AnimationState::NotRunning => "Animation: Not running",
         });
         let maze_block_title = Title::from("Maze crawler".bold());
-        Block::default()
-            .title(maze_block_title)
+        let seed_title = Title::from(format!("Seed: {}", self.seed));
+        let mut block = Block::default().title(maze_block_title).title(seed_title);
+        if let Some((level, levels)) = self.level {
+            block = block.title(format!("Level: {}/{}", level + 1, levels));
+        }
+        block
             .title(state_title)
             .title(animation_state_title)
             .borders(Borders::ALL)
@@ -54,14 +144,24 @@ impl Widget for MazeGrid {
 
         for col in 0..cols {
             for row in 0..rows {
-                let (value, color) = match self.maze.get_cell(Point { x: col, y: row }).get_type() {
+                let cell = self.maze.get_cell(Point { x: col, y: row });
+                let terrain = match cell.get_terrain() {
+                    Terrain::Normal => "  ",
+                    Terrain::SwampLite => "░░",
+                    Terrain::SwampMedium => "▒▒",
+                    Terrain::SwampHeavy => "▓▓",
+                };
+                let (mut value, color) = match cell.get_type() {
                     CellType::Wall => ("██", Style::default().on_black().white()),
-                    CellType::Path => ("  ", Style::default().on_black()),
-                    CellType::Entrance => ("░░", Style::default().blue()),
+                    CellType::Path => (terrain, Style::default().on_black().yellow()),
+                    CellType::Entrance => ("╘╛", Style::default().blue()),
                     CellType::Exit => ("╒╕", Style::default().red()),
-                    CellType::Visited => ("  ", Style::default().on_light_yellow()),
-                    CellType::FinalPath => ("  ", Style::default().on_light_green()),
+                    CellType::Visited => (terrain, Style::default().on_light_yellow().black()),
+                    CellType::FinalPath => (terrain, Style::default().on_light_green().black()),
                 };
+                if let Some(marker) = self.stairs.get(&Point { x: col, y: row }) {
+                    value = marker;
+                }
                 buf.set_string(
                     area.left() + 1 + (col * 2) as u16,
                     area.top() + 1 + row as u16,